@@ -0,0 +1,23 @@
+use soroban_sdk::{Address, Bytes, BytesN, Env, Vec, xdr::ToXdr};
+
+// Allowlist Merkle trees hash each address's XDR encoding into a leaf and
+// combine siblings in sorted order, so proofs carry no left/right flags.
+
+pub fn leaf(env: &Env, address: &Address) -> BytesN<32> {
+    env.crypto().sha256(&address.clone().to_xdr(env)).into()
+}
+
+pub fn hash_pair(env: &Env, a: &BytesN<32>, b: &BytesN<32>) -> BytesN<32> {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut data = Bytes::from_array(env, &first.to_array());
+    data.extend_from_array(&second.to_array());
+    env.crypto().sha256(&data).into()
+}
+
+pub fn verify(env: &Env, root: &BytesN<32>, address: &Address, proof: &Vec<BytesN<32>>) -> bool {
+    let mut hash = leaf(env, address);
+    for node in proof.iter() {
+        hash = hash_pair(env, &hash, &node);
+    }
+    hash == *root
+}
@@ -1,8 +1,30 @@
+use crate::allowlist;
+use crate::enumeration;
 use crate::error::ContractError;
 use crate::events;
-use crate::storage::DataKey;
-use crate::types::{CollectionConfig, RoyaltyInfo, TokenMetadata};
-use soroban_sdk::{Address, Env, String, Vec, contract, contractimpl, panic_with_error};
+use crate::interface::{NftReceiverClient, NonFungibleToken};
+use crate::provenance;
+use crate::snapshot;
+use crate::storage::{self, DataKey, MetadataKey, ProvenanceKey};
+use crate::types::{
+    CollectionConfig, MintPhase, MintVoucher, MinterLimits, OperatorPolicy, ProvenanceEntry,
+    PublicMintConfig, Role, RoyaltyInfo, TokenApproval, TokenHistory, TokenMetadata, UserInfo,
+};
+use crate::uri;
+use soroban_sdk::{
+    Address, Bytes, BytesN, Env, Executable, String, Symbol, Vec, contract, contractimpl,
+    panic_with_error, token, xdr::ToXdr,
+};
+
+const ROLES: [Role; 4] = [
+    Role::Minter,
+    Role::Pauser,
+    Role::MetadataAdmin,
+    Role::RoyaltyAdmin,
+];
+
+// Bounds the instance entry and the ranges `next_token_id` hops over
+const MAX_RESERVED_RANGES: u32 = 16;
 
 #[contract]
 pub struct NftCollection;
@@ -14,275 +36,1988 @@ impl NftCollection {
             panic_with_error!(&env, ContractError::AlreadyInitialized);
         }
 
-        env.storage().instance().set(&DataKey::FactoryAdmin, &admin);
+        env.storage()
+            .instance()
+            .set(&DataKey::CollectionOwner, &admin);
         env.storage()
             .instance()
             .set(&DataKey::CollectionConfig, &config);
         env.storage().instance().set(&DataKey::TotalSupply, &0u32);
         env.storage().instance().set(&DataKey::IsPaused, &false);
+        env.storage()
+            .instance()
+            .set(&DataKey::Revealed, &config.placeholder_uri.is_none());
+        env.storage()
+            .instance()
+            .set(&DataKey::MetadataFrozen, &false);
 
         let royalty_info = RoyaltyInfo {
             recipient: config.royalty_recipient.clone(),
             percentage: config.royalty_percentage,
+            splits: Vec::new(&env),
         };
         env.storage()
             .instance()
             .set(&DataKey::RoyaltyInfo, &royalty_info);
 
-        // Admin is the initial minter
-        env.storage().instance().set(&DataKey::Minter(admin), &true);
+        // The initial owner starts out holding every role
+        for role in ROLES {
+            Self::grant(&env, role, admin.clone(), admin.clone());
+        }
+        storage::extend_instance(&env);
+
+        if let Some(hash) = config.provenance_hash {
+            events::emit_provenance_committed(&env, hash);
+        }
     }
 
+    /// Mints `token_id` to `to`. `minter` must hold the minter role and is
+    /// recorded as the token's creator. `content_hash` pins the sha256 of the
+    /// media behind `uri`.
     pub fn mint(
         env: Env,
+        minter: Address,
         to: Address,
         token_id: u32,
         uri: String,
         attributes: Vec<(String, String)>,
+        content_hash: Option<BytesN<32>>,
     ) -> Result<(), ContractError> {
-        let admin: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::FactoryAdmin)
-            .unwrap();
-        // Allow only admin or designated minters
-        if !Self::is_minter(&env, &admin) {
-            return Err(ContractError::NotMinter);
-        }
+        Self::check_minter(&env, &minter, 1)?;
 
-        if env
-            .storage()
-            .instance()
-            .has(&DataKey::TokenMetadata(token_id))
-        {
-            return Err(ContractError::TokenAlreadyExists);
-        }
+        Self::mint_token(&env, to, token_id, uri, attributes, minter, content_hash)
+    }
 
-        let config: CollectionConfig = env
-            .storage()
-            .instance()
-            .get(&DataKey::CollectionConfig)
-            .unwrap();
-        let total_supply: u32 = env
-            .storage()
-            .instance()
-            .get(&DataKey::TotalSupply)
-            .unwrap_or(0);
+    /// Mints the id after the highest one minted so far to `to` and returns
+    /// it. Reserved ids are skipped; ids passed over by explicit mints are
+    /// not handed out.
+    pub fn mint_next(
+        env: Env,
+        minter: Address,
+        to: Address,
+        uri: String,
+        attributes: Vec<(String, String)>,
+        content_hash: Option<BytesN<32>>,
+    ) -> Result<u32, ContractError> {
+        Self::check_minter(&env, &minter, 1)?;
 
-        if let Some(max) = config.max_supply
-            && total_supply >= max
-        {
-            return Err(ContractError::SupplyLimitExceeded);
-        }
+        let token_id = Self::next_token_id(&env)?;
+        Self::mint_token(&env, to, token_id, uri, attributes, minter, content_hash)?;
 
-        let metadata = TokenMetadata {
-            token_id,
-            uri: uri.clone(),
-            attributes,
-            creator: admin.clone(),
-            created_at: env.ledger().timestamp(),
-        };
+        Ok(token_id)
+    }
+
+    /// Keeps ids `start..=end` out of `mint_next`, so they can be minted
+    /// explicitly, for example as team pieces. Minting a reserved id moves
+    /// `mint_next` past it like any other explicit mint.
+    pub fn reserve_ids(env: Env, start: u32, end: u32) -> Result<(), ContractError> {
+        Self::require_owner(&env);
 
+        if start > end {
+            return Err(ContractError::InvalidIdRange);
+        }
+        let mut reserved = Self::reserved_ids(env.clone());
+        if reserved.len() >= MAX_RESERVED_RANGES {
+            return Err(ContractError::TooManyReservedRanges);
+        }
+        reserved.push_back((start, end));
         env.storage()
             .instance()
-            .set(&DataKey::TokenMetadata(token_id), &metadata);
-        env.storage().instance().set(&DataKey::Owner(token_id), &to);
+            .set(&DataKey::ReservedIds, &reserved);
+        storage::extend_instance(&env);
 
-        let balance: u32 = env
-            .storage()
-            .instance()
-            .get(&DataKey::Balance(to.clone(), token_id))
-            .unwrap_or(0);
+        Ok(())
+    }
+
+    /// Hands the range `start..=end` added by `reserve_ids` back to
+    /// `mint_next`.
+    pub fn unreserve_ids(env: Env, start: u32, end: u32) -> Result<(), ContractError> {
+        Self::require_owner(&env);
+
+        let mut reserved = Self::reserved_ids(env.clone());
+        let index = reserved
+            .first_index_of((start, end))
+            .ok_or(ContractError::InvalidIdRange)?;
+        reserved.remove(index);
         env.storage()
             .instance()
-            .set(&DataKey::Balance(to.clone(), token_id), &(balance + 1));
+            .set(&DataKey::ReservedIds, &reserved);
+        storage::extend_instance(&env);
+
+        Ok(())
+    }
 
+    /// Inclusive id ranges skipped by `mint_next`.
+    pub fn reserved_ids(env: Env) -> Vec<(u32, u32)> {
         env.storage()
             .instance()
-            .set(&DataKey::TotalSupply, &(total_supply + 1));
+            .get(&DataKey::ReservedIds)
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Whether `token_id` was burned. Burned ids can't be minted again.
+    pub fn is_burned(env: Env, token_id: u32) -> bool {
+        // Burned tokens keep their provenance log
+        !storage::has_persistent(&env, &DataKey::TokenMetadata(token_id))
+            && provenance::exists(&env, token_id)
+    }
+
+    /// Mints `token_ids[i]` to `to[i]` with `uris[i]` and `attributes[i]`.
+    /// Either every token is minted or none is.
+    pub fn batch_mint(
+        env: Env,
+        minter: Address,
+        to: Vec<Address>,
+        token_ids: Vec<u32>,
+        uris: Vec<String>,
+        attributes: Vec<Vec<(String, String)>>,
+    ) -> Result<(), ContractError> {
+        let count = token_ids.len();
+        if count == 0 || to.len() != count || uris.len() != count || attributes.len() != count {
+            return Err(ContractError::InvalidAmount);
+        }
+        Self::check_minter(&env, &minter, count)?;
+        Self::check_supply(&env, count)?;
+
+        for i in 0..count {
+            let recipient = to.get_unchecked(i);
+            let token_id = token_ids.get_unchecked(i);
+            Self::write_token(
+                &env,
+                &recipient,
+                token_id,
+                uris.get_unchecked(i),
+                attributes.get_unchecked(i),
+                minter.clone(),
+                None,
+            )?;
+            enumeration::add_token(&env, &recipient, token_id);
+        }
 
-        events::emit_mint(&env, env.current_contract_address(), to, token_id, 1);
+        storage::extend_instance(&env);
+        events::emit_batch_mint(&env, to, token_ids);
 
         Ok(())
     }
 
-    pub fn transfer(
+    /// Transfers every token in `token_ids` from `from` to `to`, or none if
+    /// any of them is not owned by `from`.
+    pub fn batch_transfer(
         env: Env,
         from: Address,
         to: Address,
-        token_id: u32,
+        token_ids: Vec<u32>,
     ) -> Result<(), ContractError> {
         from.require_auth();
 
-        if env
-            .storage()
-            .instance()
-            .get::<_, bool>(&DataKey::IsPaused)
-            .unwrap_or(false)
-        {
-            return Err(ContractError::ContractPaused);
+        if token_ids.is_empty() {
+            return Err(ContractError::InvalidAmount);
         }
+        Self::check_direct_transfer(&env, &from, &to)?;
 
-        let owner: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::Owner(token_id))
-            .ok_or(ContractError::NotFound)?;
-        if owner != from {
-            return Err(ContractError::NotAuthorized);
+        for token_id in token_ids.iter() {
+            if Self::owner(&env, token_id)? != from {
+                return Err(ContractError::NotAuthorized);
+            }
+            Self::move_token(&env, &from, from.clone(), to.clone(), token_id)?;
         }
 
-        env.storage().instance().set(&DataKey::Owner(token_id), &to);
+        events::emit_batch_transfer(&env, from, to, token_ids);
 
-        let from_balance: u32 = env
-            .storage()
-            .instance()
-            .get(&DataKey::Balance(from.clone(), token_id))
-            .unwrap_or(0);
-        env.storage().instance().set(
-            &DataKey::Balance(from.clone(), token_id),
-            &(from_balance - 1),
-        );
+        Ok(())
+    }
 
-        let to_balance: u32 = env
-            .storage()
-            .instance()
-            .get(&DataKey::Balance(to.clone(), token_id))
-            .unwrap_or(0);
-        env.storage()
-            .instance()
-            .set(&DataKey::Balance(to.clone(), token_id), &(to_balance + 1));
+    /// Like `mint`, but a contract recipient must accept the token through
+    /// `on_nft_received`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn safe_mint(
+        env: Env,
+        minter: Address,
+        to: Address,
+        token_id: u32,
+        uri: String,
+        attributes: Vec<(String, String)>,
+        content_hash: Option<BytesN<32>>,
+        data: Bytes,
+    ) -> Result<(), ContractError> {
+        Self::mint(
+            env.clone(),
+            minter.clone(),
+            to.clone(),
+            token_id,
+            uri,
+            attributes,
+            content_hash,
+        )?;
+        Self::check_receiver(&env, minter, None, to, token_id, data)
+    }
 
-        events::emit_transfer(&env, env.current_contract_address(), from, to, token_id, 1);
+    /// Like `transfer`, but a contract recipient must accept the token
+    /// through `on_nft_received`.
+    pub fn safe_transfer(
+        env: Env,
+        from: Address,
+        to: Address,
+        token_id: u32,
+        data: Bytes,
+    ) -> Result<(), ContractError> {
+        <Self as NonFungibleToken>::transfer(env.clone(), from.clone(), to.clone(), token_id)?;
+        Self::check_receiver(&env, from.clone(), Some(from), to, token_id, data)
+    }
 
-        Ok(())
+    /// Like `transfer_from`, but a contract recipient must accept the token
+    /// through `on_nft_received`.
+    pub fn safe_transfer_from(
+        env: Env,
+        spender: Address,
+        from: Address,
+        to: Address,
+        token_id: u32,
+        data: Bytes,
+    ) -> Result<(), ContractError> {
+        <Self as NonFungibleToken>::transfer_from(
+            env.clone(),
+            spender.clone(),
+            from.clone(),
+            to.clone(),
+            token_id,
+        )?;
+        Self::check_receiver(&env, spender, Some(from), to, token_id, data)
     }
 
-    pub fn burn(env: Env, from: Address, token_id: u32) -> Result<(), ContractError> {
-        from.require_auth();
+    /// Configures paid public minting. Only takes effect while the
+    /// collection's `is_public_mint` flag is set.
+    pub fn set_public_mint(env: Env, config: PublicMintConfig) -> Result<(), ContractError> {
+        Self::require_owner(&env);
 
-        let owner: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::Owner(token_id))
-            .ok_or(ContractError::NotFound)?;
-        if owner != from {
-            return Err(ContractError::NotAuthorized);
+        if config.price < 0 {
+            return Err(ContractError::InvalidAmount);
         }
 
-        env.storage().instance().remove(&DataKey::Owner(token_id));
         env.storage()
             .instance()
-            .remove(&DataKey::TokenMetadata(token_id));
+            .set(&DataKey::PublicMintConfig, &config);
+
+        Ok(())
+    }
+
+    pub fn get_public_mint(env: Env) -> Option<PublicMintConfig> {
+        env.storage().instance().get(&DataKey::PublicMintConfig)
+    }
+
+    /// Number of tokens `wallet` has minted through `public_mint`.
+    pub fn public_mint_count(env: Env, wallet: Address) -> u32 {
+        storage::get_persistent(&env, &DataKey::MintCount(wallet)).unwrap_or(0)
+    }
+
+    /// Mints the next free token id to `minter`, who pays the mint price to
+    /// the configured payout address, and returns the id.
+    pub fn public_mint(env: Env, minter: Address) -> Result<u32, ContractError> {
+        minter.require_auth();
 
-        let balance: u32 = env
+        let config: CollectionConfig = env
             .storage()
             .instance()
-            .get(&DataKey::Balance(from.clone(), token_id))
-            .unwrap_or(0);
-        env.storage()
-            .instance()
-            .set(&DataKey::Balance(from.clone(), token_id), &(balance - 1));
+            .get(&DataKey::CollectionConfig)
+            .unwrap();
+        if !config.is_public_mint {
+            return Err(ContractError::PublicMintDisabled);
+        }
 
-        let total_supply: u32 = env.storage().instance().get(&DataKey::TotalSupply).unwrap();
-        env.storage()
-            .instance()
-            .set(&DataKey::TotalSupply, &(total_supply - 1));
+        // With phases configured, public minting is limited to public phases
+        let phase = Self::active_phase(&env)?;
+        if phase.as_ref().is_some_and(|(_, phase)| phase.allowlist) {
+            return Err(ContractError::MintPhaseNotActive);
+        }
 
-        events::emit_burn(&env, env.current_contract_address(), from, token_id, 1);
+        Self::paid_mint(&env, minter, phase)
+    }
 
-        Ok(())
+    /// Mints during an allowlist phase. `proof` is only needed for addresses
+    /// covered by the phase's Merkle root rather than an allowlist entry.
+    pub fn allowlist_mint(
+        env: Env,
+        minter: Address,
+        proof: Vec<BytesN<32>>,
+    ) -> Result<u32, ContractError> {
+        minter.require_auth();
+
+        let (index, phase) = Self::active_phase(&env)?.ok_or(ContractError::MintPhaseNotActive)?;
+        if !phase.allowlist {
+            return Err(ContractError::MintPhaseNotActive);
+        }
+
+        let listed = storage::get_persistent(&env, &DataKey::Whitelist(minter.clone()))
+            .unwrap_or(false)
+            || phase
+                .merkle_root
+                .as_ref()
+                .is_some_and(|root| allowlist::verify(&env, root, &minter, &proof));
+        if !listed {
+            return Err(ContractError::NotAllowlisted);
+        }
+
+        Self::paid_mint(&env, minter, Some((index, phase)))
     }
 
-    pub fn get_token_uri(env: Env, token_id: u32) -> Option<String> {
-        let metadata: TokenMetadata = env
-            .storage()
-            .instance()
-            .get(&DataKey::TokenMetadata(token_id))?;
-        Some(metadata.uri)
+    /// Replaces the mint schedule. Phases must be ordered and must not
+    /// overlap; an empty list removes the schedule.
+    pub fn set_mint_phases(env: Env, phases: Vec<MintPhase>) -> Result<(), ContractError> {
+        Self::require_owner(&env);
+
+        let mut previous_end = 0;
+        for phase in phases.iter() {
+            if phase.start_time >= phase.end_time
+                || phase.start_time < previous_end
+                || phase.price < 0
+            {
+                return Err(ContractError::InvalidMintPhase);
+            }
+            previous_end = phase.end_time;
+        }
+
+        if phases.is_empty() {
+            env.storage().instance().remove(&DataKey::MintPhases);
+        } else {
+            env.storage().instance().set(&DataKey::MintPhases, &phases);
+        }
+
+        Ok(())
     }
 
-    pub fn get_token_metadata(env: Env, token_id: u32) -> Option<TokenMetadata> {
+    pub fn get_mint_phases(env: Env) -> Vec<MintPhase> {
         env.storage()
             .instance()
-            .get(&DataKey::TokenMetadata(token_id))
+            .get(&DataKey::MintPhases)
+            .unwrap_or(Vec::new(&env))
     }
 
-    pub fn total_supply(env: Env) -> u32 {
+    /// Adds or removes explicit allowlist entries, for lists small enough
+    /// not to need a Merkle root.
+    pub fn set_allowlist(env: Env, addresses: Vec<Address>, allowed: bool) {
+        Self::require_owner(&env);
+
+        for address in addresses.iter() {
+            let key = DataKey::Whitelist(address);
+            if allowed {
+                storage::set_persistent(&env, &key, &true);
+            } else {
+                storage::remove_persistent(&env, &key);
+            }
+        }
+    }
+
+    pub fn is_allowlisted(env: Env, address: Address) -> bool {
+        storage::get_persistent(&env, &DataKey::Whitelist(address)).unwrap_or(false)
+    }
+
+    pub fn set_operator_policy(env: Env, policy: OperatorPolicy) {
+        Self::require_owner(&env);
         env.storage()
             .instance()
-            .get(&DataKey::TotalSupply)
-            .unwrap_or(0)
+            .set(&DataKey::OperatorPolicy, &policy);
     }
 
-    pub fn balance_of(env: Env, owner: Address, token_id: u32) -> u32 {
+    pub fn get_operator_policy(env: Env) -> OperatorPolicy {
         env.storage()
             .instance()
-            .get(&DataKey::Balance(owner, token_id))
-            .unwrap_or(0)
+            .get(&DataKey::OperatorPolicy)
+            .unwrap_or_default()
     }
 
-    pub fn owner_of(env: Env, token_id: u32) -> Option<Address> {
-        env.storage().instance().get(&DataKey::Owner(token_id))
+    /// Adds or removes a marketplace contract from the operator allowlist.
+    pub fn set_allowed_operator(env: Env, operator: Address, allowed: bool) {
+        Self::require_owner(&env);
+
+        let key = DataKey::AllowedOperator(operator.clone());
+        if allowed {
+            storage::set_persistent(&env, &key, &true);
+        } else {
+            storage::remove_persistent(&env, &key);
+        }
+
+        events::emit_operator_allowed(&env, operator, allowed);
     }
 
-    pub fn set_royalty_info(
+    pub fn is_allowed_operator(env: Env, operator: Address) -> bool {
+        storage::get_persistent(&env, &DataKey::AllowedOperator(operator)).unwrap_or(false)
+    }
+
+    /// Number of tokens `wallet` has minted during mint phase `phase`.
+    pub fn phase_mint_count(env: Env, phase: u32, wallet: Address) -> u32 {
+        storage::get_persistent(&env, &DataKey::PhaseMintCount(phase, wallet)).unwrap_or(0)
+    }
+
+    /// Authorizes or revokes an ed25519 key for signing lazy-mint vouchers.
+    /// Lets `public_key` sign vouchers on behalf of `minter`, or unlinks it
+    /// with `None`. Redeemed vouchers count against the minter's quota and
+    /// record it as the creator.
+    pub fn set_voucher_signer(env: Env, public_key: BytesN<32>, minter: Option<Address>) {
+        Self::require_owner(&env);
+
+        let key = DataKey::VoucherSigner(public_key);
+        match minter {
+            Some(minter) => storage::set_persistent(&env, &key, &minter),
+            None => storage::remove_persistent(&env, &key),
+        }
+    }
+
+    pub fn is_voucher_signer(env: Env, public_key: BytesN<32>) -> bool {
+        storage::has_persistent(&env, &DataKey::VoucherSigner(public_key))
+    }
+
+    /// Minter `public_key` signs vouchers for.
+    pub fn voucher_signer_minter(env: Env, public_key: BytesN<32>) -> Option<Address> {
+        storage::get_persistent(&env, &DataKey::VoucherSigner(public_key))
+    }
+
+    /// Bytes a signer signs for `voucher`. They bind the voucher to this
+    /// collection so it cannot be replayed on another one.
+    pub fn voucher_message(env: Env, voucher: MintVoucher) -> Bytes {
+        (env.current_contract_address(), voucher).to_xdr(&env)
+    }
+
+    /// Mints the voucher's token to `redeemer`, who pays the voucher price
+    /// in the voucher's payment token to its payout address. The signer's
+    /// minter must still hold the minter role and have quota left.
+    pub fn redeem_voucher(
         env: Env,
-        recipient: Address,
-        percentage: u32,
+        redeemer: Address,
+        voucher: MintVoucher,
+        signer: BytesN<32>,
+        signature: BytesN<64>,
     ) -> Result<(), ContractError> {
-        let admin: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::FactoryAdmin)
-            .unwrap();
-        admin.require_auth();
+        redeemer.require_auth();
 
-        if percentage > 10000 {
-            return Err(ContractError::InvalidRoyalty);
+        let minter = Self::voucher_signer_minter(env.clone(), signer.clone())
+            .ok_or(ContractError::NotMinter)?;
+        if env.ledger().timestamp() > voucher.expiry {
+            return Err(ContractError::VoucherExpired);
+        }
+        let nonce_key = DataKey::VoucherNonce(signer.clone(), voucher.nonce);
+        if storage::has_persistent(&env, &nonce_key) {
+            return Err(ContractError::VoucherAlreadyUsed);
+        }
+        if voucher.price < 0 {
+            return Err(ContractError::InvalidAmount);
+        }
+        if Self::is_paused(&env) {
+            return Err(ContractError::ContractPaused);
         }
 
-        let royalty_info = RoyaltyInfo {
-            recipient,
-            percentage,
-        };
-        env.storage()
-            .instance()
-            .set(&DataKey::RoyaltyInfo, &royalty_info);
+        // Traps on a bad signature
+        let message = Self::voucher_message(env.clone(), voucher.clone());
+        env.crypto().ed25519_verify(&signer, &message, &signature);
+
+        storage::set_persistent(&env, &nonce_key, &true);
+
+        Self::count_mints(&env, &minter, 1)?;
+        Self::mint_token(
+            &env,
+            redeemer.clone(),
+            voucher.token_id,
+            voucher.uri,
+            voucher.attributes,
+            minter,
+            voucher.content_hash,
+        )?;
+
+        if voucher.price > 0 {
+            let payment = token::Client::new(&env, &voucher.payment_token);
+            if payment
+                .try_transfer(&redeemer, &voucher.payout, &voucher.price)
+                .is_err()
+            {
+                return Err(ContractError::InsufficientBalance);
+            }
+        }
 
         Ok(())
     }
 
-    pub fn get_royalty_info(env: Env) -> RoyaltyInfo {
-        env.storage().instance().get(&DataKey::RoyaltyInfo).unwrap()
+    /// Invalidates an unredeemed voucher issued by `signer`. The collection
+    /// owner and the minter `signer` signs for may cancel.
+    pub fn cancel_voucher(
+        env: Env,
+        caller: Address,
+        signer: BytesN<32>,
+        nonce: u64,
+    ) -> Result<(), ContractError> {
+        caller.require_auth();
+
+        if caller != Self::get_owner(env.clone())
+            && Self::voucher_signer_minter(env.clone(), signer.clone()) != Some(caller)
+        {
+            return Err(ContractError::NotAuthorized);
+        }
+
+        let nonce_key = DataKey::VoucherNonce(signer.clone(), nonce);
+        if storage::has_persistent(&env, &nonce_key) {
+            return Err(ContractError::VoucherAlreadyUsed);
+        }
+        storage::set_persistent(&env, &nonce_key, &true);
+
+        events::emit_voucher_cancelled(&env, signer, nonce);
+
+        Ok(())
+    }
+
+    pub fn is_voucher_used(env: Env, signer: BytesN<32>, nonce: u64) -> bool {
+        storage::has_persistent(&env, &DataKey::VoucherNonce(signer, nonce))
+    }
+
+    /// Grants or revokes an operator approval that lasts until revoked.
+    pub fn set_approval_for_all(
+        env: Env,
+        owner: Address,
+        operator: Address,
+        approved: bool,
+    ) -> Result<(), ContractError> {
+        owner.require_auth();
+
+        if owner == operator {
+            return Err(ContractError::InvalidRecipient);
+        }
+
+        if approved && Self::is_soulbound(env.clone()) {
+            return Err(ContractError::Soulbound);
+        }
+        if approved {
+            Self::check_operator(&env, &operator)?;
+        }
+
+        let live_until_ledger = if approved { u32::MAX } else { 0 };
+        Self::set_operator(&env, owner, operator, live_until_ledger);
+
+        Ok(())
+    }
+
+    pub fn burn(env: Env, from: Address, token_id: u32) -> Result<(), ContractError> {
+        from.require_auth();
+
+        if Self::owner(&env, token_id)? != from {
+            return Err(ContractError::NotAuthorized);
+        }
+
+        Self::burn_token(&env, &from.clone(), from, token_id)
     }
 
-    pub fn set_pause(env: Env, paused: bool) {
-        let admin: Address = env
+    /// Lets the collection owner take back a soulbound token, when the
+    /// collection was created as revocable.
+    pub fn revoke_token(env: Env, token_id: u32) -> Result<(), ContractError> {
+        let owner = Self::require_owner(&env);
+
+        let config: CollectionConfig = env
             .storage()
             .instance()
-            .get(&DataKey::FactoryAdmin)
+            .get(&DataKey::CollectionConfig)
             .unwrap();
-        admin.require_auth();
-        env.storage().instance().set(&DataKey::IsPaused, &paused);
+        if !config.soulbound || !config.revocable {
+            return Err(ContractError::NotAuthorized);
+        }
+
+        let holder = Self::owner(&env, token_id)?;
+        Self::burn_token(&env, &owner, holder, token_id)
     }
 
-    pub fn set_minter(env: Env, minter: Address, is_minter: bool) {
-        let admin: Address = env
+    /// Whether tokens in this collection are bound to their first holder.
+    pub fn is_soulbound(env: Env) -> bool {
+        let config: CollectionConfig = env
             .storage()
             .instance()
-            .get(&DataKey::FactoryAdmin)
+            .get(&DataKey::CollectionConfig)
             .unwrap();
-        admin.require_auth();
-        env.storage()
-            .instance()
-            .set(&DataKey::Minter(minter), &is_minter);
+        config.soulbound
     }
 
-    fn is_minter(env: &Env, address: &Address) -> bool {
-        env.storage()
-            .instance()
-            .get(&DataKey::Minter(address.clone()))
-            .unwrap_or(false)
+    /// Records the current holders under a new snapshot id, for airdrops
+    /// and votes.
+    pub fn snapshot(env: Env) -> u32 {
+        Self::require_owner(&env);
+
+        let id = snapshot::current(&env) + 1;
+        env.storage().instance().set(&DataKey::CurrentSnapshot, &id);
+        events::emit_snapshot(&env, id, env.ledger().timestamp());
+
+        id
+    }
+
+    pub fn owner_at(env: Env, token_id: u32, snapshot_id: u32) -> Result<Address, ContractError> {
+        match snapshot::owner_at(&env, token_id, snapshot_id)? {
+            Some(owner) => owner.ok_or(ContractError::NotFound),
+            None => Self::owner(&env, token_id),
+        }
+    }
+
+    pub fn balance_at(env: Env, owner: Address, snapshot_id: u32) -> Result<u32, ContractError> {
+        match snapshot::balance_at(&env, &owner, snapshot_id)? {
+            Some(balance) => Ok(balance),
+            None => Ok(<Self as NonFungibleToken>::balance(env, owner)),
+        }
+    }
+
+    /// Rents `token_id` out to `user` until `expires` without moving it.
+    /// A `None` user ends the rental. Callable by the token owner or an
+    /// approved operator.
+    pub fn set_user(
+        env: Env,
+        caller: Address,
+        token_id: u32,
+        user: Option<Address>,
+        expires: u64,
+    ) -> Result<(), ContractError> {
+        caller.require_auth();
+
+        let owner = Self::owner(&env, token_id)?;
+        if !Self::is_approved_or_owner(&env, &caller, &owner, token_id) {
+            return Err(ContractError::NotApproved);
+        }
+
+        let key = DataKey::User(token_id);
+        let expires = match &user {
+            Some(user) => {
+                let info = UserInfo {
+                    user: user.clone(),
+                    expires,
+                };
+                storage::set_persistent(&env, &key, &info);
+                expires
+            }
+            None => {
+                storage::remove_persistent(&env, &key);
+                0
+            }
+        };
+        events::emit_update_user(&env, token_id, user, expires);
+
+        Ok(())
+    }
+
+    /// The current renter of `token_id`, if the rental hasn't expired.
+    pub fn user_of(env: Env, token_id: u32) -> Option<Address> {
+        storage::get_persistent::<UserInfo>(&env, &DataKey::User(token_id))
+            .filter(|info| env.ledger().timestamp() < info.expires)
+            .map(|info| info.user)
+    }
+
+    /// When the rental of `token_id` ends, or 0 without a rental.
+    pub fn user_expires(env: Env, token_id: u32) -> u64 {
+        storage::get_persistent::<UserInfo>(&env, &DataKey::User(token_id))
+            .map_or(0, |info| info.expires)
+    }
+
+    /// Approves or removes a contract, such as an escrow or lending pool,
+    /// that may lock tokens.
+    pub fn set_locker(env: Env, locker: Address, allowed: bool) {
+        Self::require_owner(&env);
+
+        let key = DataKey::Locker(locker);
+        if allowed {
+            storage::set_persistent(&env, &key, &true);
+        } else {
+            storage::remove_persistent(&env, &key);
+        }
+    }
+
+    pub fn is_locker(env: Env, locker: Address) -> bool {
+        storage::get_persistent(&env, &DataKey::Locker(locker)).unwrap_or(false)
+    }
+
+    /// Locks `token_id` so it can't be transferred or burned until `locker`
+    /// unlocks it. `locker` must be an approved locker and hold or be
+    /// approved for the token.
+    pub fn lock(env: Env, locker: Address, token_id: u32) -> Result<(), ContractError> {
+        locker.require_auth();
+
+        if !Self::is_locker(env.clone(), locker.clone()) {
+            return Err(ContractError::NotAuthorized);
+        }
+        let owner = Self::owner(&env, token_id)?;
+        if !Self::is_approved_or_owner(&env, &locker, &owner, token_id) {
+            return Err(ContractError::NotApproved);
+        }
+        if Self::is_locked(env.clone(), token_id) {
+            return Err(ContractError::TokenLocked);
+        }
+
+        storage::set_persistent(&env, &DataKey::LockedBy(token_id), &locker);
+        events::emit_lock(&env, locker, token_id);
+
+        Ok(())
+    }
+
+    pub fn unlock(env: Env, locker: Address, token_id: u32) -> Result<(), ContractError> {
+        locker.require_auth();
+
+        if Self::locked_by(env.clone(), token_id) != Some(locker.clone()) {
+            return Err(ContractError::NotAuthorized);
+        }
+
+        storage::remove_persistent(&env, &DataKey::LockedBy(token_id));
+        events::emit_unlock(&env, locker, token_id);
+
+        Ok(())
+    }
+
+    pub fn is_locked(env: Env, token_id: u32) -> bool {
+        storage::has_persistent(&env, &DataKey::LockedBy(token_id))
+    }
+
+    pub fn locked_by(env: Env, token_id: u32) -> Option<Address> {
+        storage::get_persistent(&env, &DataKey::LockedBy(token_id))
+    }
+
+    /// Sets the final base URI and stops serving the placeholder. Can only
+    /// happen once.
+    pub fn reveal(env: Env, caller: Address, base_uri: String) -> Result<(), ContractError> {
+        Self::require_role(&env, &caller, Role::MetadataAdmin)?;
+
+        if Self::is_metadata_frozen(env.clone()) {
+            return Err(ContractError::MetadataFrozen);
+        }
+        if Self::is_revealed(env.clone()) {
+            return Err(ContractError::AlreadyRevealed);
+        }
+
+        let mut config: CollectionConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::CollectionConfig)
+            .unwrap();
+        config.base_uri = base_uri.clone();
+        env.storage()
+            .instance()
+            .set(&DataKey::CollectionConfig, &config);
+        env.storage().instance().set(&DataKey::Revealed, &true);
+
+        events::emit_reveal(&env, base_uri);
+        // Every token without its own URI changes at once
+        events::emit_batch_metadata_update(&env, 0, u32::MAX);
+
+        Ok(())
+    }
+
+    /// Permanently locks token URIs and attributes.
+    pub fn freeze_metadata(env: Env, caller: Address) -> Result<(), ContractError> {
+        Self::require_role(&env, &caller, Role::MetadataAdmin)?;
+
+        if Self::is_metadata_frozen(env.clone()) {
+            return Err(ContractError::MetadataFrozen);
+        }
+        if !Self::is_revealed(env.clone()) {
+            return Err(ContractError::NotRevealed);
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::MetadataFrozen, &true);
+
+        let config: CollectionConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::CollectionConfig)
+            .unwrap();
+        events::emit_metadata_frozen(&env, config.base_uri);
+
+        Ok(())
+    }
+
+    /// Serves token URIs as JSON documents built on-chain from the collection
+    /// name, description and token attributes. An `svg_template` renders
+    /// the attributes into the image.
+    pub fn set_onchain_metadata(
+        env: Env,
+        caller: Address,
+        enabled: bool,
+        svg_template: Option<String>,
+    ) -> Result<(), ContractError> {
+        Self::require_role(&env, &caller, Role::MetadataAdmin)?;
+
+        if Self::is_metadata_frozen(env.clone()) {
+            return Err(ContractError::MetadataFrozen);
+        }
+
+        env.storage()
+            .instance()
+            .set(&MetadataKey::OnchainMetadata, &enabled);
+        match svg_template {
+            Some(template) => storage::set_persistent(&env, &MetadataKey::SvgTemplate, &template),
+            None => storage::remove_persistent(&env, &MetadataKey::SvgTemplate),
+        }
+
+        events::emit_batch_metadata_update(&env, 0, u32::MAX);
+
+        Ok(())
+    }
+
+    pub fn is_onchain_metadata(env: Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&MetadataKey::OnchainMetadata)
+            .unwrap_or(false)
+    }
+
+    pub fn svg_template(env: Env) -> Option<String> {
+        storage::get_persistent(&env, &MetadataKey::SvgTemplate)
+    }
+
+    pub fn is_revealed(env: Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::Revealed)
+            .unwrap_or(true)
+    }
+
+    pub fn is_metadata_frozen(env: Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::MetadataFrozen)
+            .unwrap_or(false)
+    }
+
+    pub fn provenance_hash(env: Env) -> Option<BytesN<32>> {
+        let config: CollectionConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::CollectionConfig)
+            .unwrap();
+        config.provenance_hash
+    }
+
+    pub fn get_token_uri(env: Env, token_id: u32) -> Option<String> {
+        <Self as NonFungibleToken>::token_uri(env, token_id).ok()
+    }
+
+    pub fn get_token_metadata(env: Env, token_id: u32) -> Option<TokenMetadata> {
+        storage::get_persistent(&env, &DataKey::TokenMetadata(token_id))
+    }
+
+    pub fn total_supply(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::TotalSupply)
+            .unwrap_or(0)
+    }
+
+    /// Token ids held by `owner`, in no particular order.
+    pub fn tokens_of_owner(env: Env, owner: Address, start: u32, limit: u32) -> Vec<u32> {
+        let balance = <Self as NonFungibleToken>::balance(env.clone(), owner.clone());
+        let end = start.saturating_add(limit).min(balance);
+
+        let mut token_ids = Vec::new(&env);
+        for index in start..end {
+            let key = DataKey::OwnedToken(owner.clone(), index);
+            if let Some(token_id) = storage::get_persistent(&env, &key) {
+                token_ids.push_back(token_id);
+            }
+        }
+        token_ids
+    }
+
+    /// The token at `index` in the collection's token list. Burns move the
+    /// last token into the burned token's slot.
+    pub fn token_by_index(env: Env, index: u32) -> Result<u32, ContractError> {
+        storage::get_persistent(&env, &DataKey::TokenByIndex(index)).ok_or(ContractError::NotFound)
+    }
+
+    /// Every existing token id, in no particular order.
+    pub fn token_ids(env: Env, start: u32, limit: u32) -> Vec<u32> {
+        let end = start
+            .saturating_add(limit)
+            .min(Self::total_supply(env.clone()));
+
+        let mut token_ids = Vec::new(&env);
+        for index in start..end {
+            if let Some(token_id) = storage::get_persistent(&env, &DataKey::TokenByIndex(index)) {
+                token_ids.push_back(token_id);
+            }
+        }
+        token_ids
+    }
+
+    /// Returns 1 if `owner` holds `token_id`, 0 otherwise.
+    pub fn balance_of(env: Env, owner: Address, token_id: u32) -> u32 {
+        storage::get_persistent::<Address>(&env, &DataKey::Owner(token_id))
+            .is_some_and(|holder| holder == owner) as u32
+    }
+
+    /// Sets the collection-wide royalty, paid to `recipient` in full.
+    pub fn set_royalty_info(
+        env: Env,
+        caller: Address,
+        recipient: Address,
+        percentage: u32,
+    ) -> Result<(), ContractError> {
+        Self::set_royalty(
+            env.clone(),
+            caller,
+            RoyaltyInfo {
+                recipient,
+                percentage,
+                splits: Vec::new(&env),
+            },
+        )
+    }
+
+    /// Sets the collection-wide royalty, including any recipient splits.
+    pub fn set_royalty(
+        env: Env,
+        caller: Address,
+        royalty: RoyaltyInfo,
+    ) -> Result<(), ContractError> {
+        Self::require_role(&env, &caller, Role::RoyaltyAdmin)?;
+
+        Self::check_royalty(&royalty)?;
+        env.storage()
+            .instance()
+            .set(&DataKey::RoyaltyInfo, &royalty);
+
+        Ok(())
+    }
+
+    pub fn get_royalty_info(env: Env) -> RoyaltyInfo {
+        env.storage().instance().get(&DataKey::RoyaltyInfo).unwrap()
+    }
+
+    /// Overrides the collection royalty for a single token.
+    pub fn set_token_royalty(
+        env: Env,
+        caller: Address,
+        token_id: u32,
+        royalty: RoyaltyInfo,
+    ) -> Result<(), ContractError> {
+        Self::require_role(&env, &caller, Role::RoyaltyAdmin)?;
+
+        Self::owner(&env, token_id)?;
+        Self::check_royalty(&royalty)?;
+        storage::set_persistent(&env, &DataKey::TokenRoyalty(token_id), &royalty);
+
+        Ok(())
+    }
+
+    /// Drops a token override so the collection royalty applies again.
+    pub fn remove_token_royalty(
+        env: Env,
+        caller: Address,
+        token_id: u32,
+    ) -> Result<(), ContractError> {
+        Self::require_role(&env, &caller, Role::RoyaltyAdmin)?;
+        storage::remove_persistent(&env, &DataKey::TokenRoyalty(token_id));
+
+        Ok(())
+    }
+
+    /// The royalty that applies to `token_id`: its override, if any,
+    /// otherwise the collection royalty.
+    pub fn get_token_royalty(env: Env, token_id: u32) -> RoyaltyInfo {
+        storage::get_persistent(&env, &DataKey::TokenRoyalty(token_id))
+            .unwrap_or_else(|| Self::get_royalty_info(env))
+    }
+
+    /// Royalty owed on a sale of `token_id` for `sale_price`, as in EIP-2981.
+    /// When the royalty is split, `recipient` is expected to forward the
+    /// shares; use `royalty_payouts` to pay each split directly.
+    pub fn royalty_info(
+        env: Env,
+        token_id: u32,
+        sale_price: i128,
+    ) -> Result<(Address, i128), ContractError> {
+        let royalty = Self::get_token_royalty(env, token_id);
+        let amount = Self::royalty_amount(&royalty, sale_price)?;
+        Ok((royalty.recipient, amount))
+    }
+
+    /// Royalty owed on a sale of `token_id` per recipient. Rounding dust
+    /// goes to the first split.
+    pub fn royalty_payouts(
+        env: Env,
+        token_id: u32,
+        sale_price: i128,
+    ) -> Result<Vec<(Address, i128)>, ContractError> {
+        let royalty = Self::get_token_royalty(env.clone(), token_id);
+        let amount = Self::royalty_amount(&royalty, sale_price)?;
+
+        let mut payouts = Vec::new(&env);
+        if amount == 0 {
+            return Ok(payouts);
+        }
+        if royalty.splits.is_empty() {
+            payouts.push_back((royalty.recipient, amount));
+            return Ok(payouts);
+        }
+
+        let mut remaining = amount;
+        for split in royalty.splits.iter() {
+            // Shares sum to 10000, so this can't overflow when `amount` didn't
+            let share = amount * split.share as i128 / 10000;
+            remaining -= share;
+            payouts.push_back((split.recipient, share));
+        }
+        let (first, share) = payouts.get_unchecked(0);
+        payouts.set(0, (first, share + remaining));
+
+        Ok(payouts)
+    }
+
+    pub fn set_pause(env: Env, caller: Address, paused: bool) -> Result<(), ContractError> {
+        Self::require_role(&env, &caller, Role::Pauser)?;
+        env.storage().instance().set(&DataKey::IsPaused, &paused);
+
+        Ok(())
+    }
+
+    pub fn get_owner(env: Env) -> Address {
+        env.storage()
+            .instance()
+            .get(&DataKey::CollectionOwner)
+            .unwrap()
+    }
+
+    pub fn get_pending_owner(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::PendingOwner)
+    }
+
+    /// Proposes `new_owner` as the collection owner. Ownership only moves
+    /// once `new_owner` accepts it.
+    pub fn transfer_ownership(env: Env, new_owner: Address) {
+        let owner = Self::require_owner(&env);
+        env.storage()
+            .instance()
+            .set(&DataKey::PendingOwner, &new_owner);
+
+        events::emit_ownership_transfer_started(&env, owner, new_owner);
+    }
+
+    pub fn accept_ownership(env: Env) -> Result<(), ContractError> {
+        let new_owner: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingOwner)
+            .ok_or(ContractError::NoPendingOwner)?;
+        new_owner.require_auth();
+
+        let previous_owner = Self::get_owner(env.clone());
+        env.storage()
+            .instance()
+            .set(&DataKey::CollectionOwner, &new_owner);
+        env.storage().instance().remove(&DataKey::PendingOwner);
+
+        // The outgoing owner's roles go with the collection
+        for role in ROLES {
+            if Self::has_role(env.clone(), role, previous_owner.clone()) {
+                Self::revoke(&env, role, previous_owner.clone(), new_owner.clone());
+                Self::grant(&env, role, new_owner.clone(), new_owner.clone());
+            }
+        }
+
+        events::emit_ownership_transferred(&env, previous_owner, new_owner);
+
+        Ok(())
+    }
+
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        storage::get_persistent(&env, &DataKey::Role(role, account)).unwrap_or(false)
+    }
+
+    pub fn grant_role(env: Env, role: Role, account: Address) {
+        let owner = Self::require_owner(&env);
+        Self::grant(&env, role, account, owner);
+    }
+
+    pub fn revoke_role(env: Env, role: Role, account: Address) {
+        let owner = Self::require_owner(&env);
+        Self::revoke(&env, role, account, owner);
+    }
+
+    /// Caps how many tokens `minter` may mint and until when. Zeroed limits
+    /// lift both caps.
+    pub fn set_minter_limits(env: Env, minter: Address, limits: MinterLimits) {
+        Self::require_owner(&env);
+        storage::set_persistent(&env, &DataKey::MinterLimits(minter), &limits);
+    }
+
+    pub fn minter_limits(env: Env, minter: Address) -> MinterLimits {
+        storage::get_persistent(&env, &DataKey::MinterLimits(minter)).unwrap_or_default()
+    }
+
+    /// Tokens `minter` has minted through `mint` and `batch_mint`.
+    pub fn minted_by(env: Env, minter: Address) -> u32 {
+        storage::get_persistent(&env, &DataKey::MinterMintCount(minter)).unwrap_or(0)
+    }
+
+    /// Lets `account` give up one of its own roles.
+    pub fn renounce_role(env: Env, role: Role, account: Address) {
+        account.require_auth();
+        Self::revoke(&env, role, account.clone(), account);
+    }
+
+    /// Replaces a token's URI, keeping the previous one in its URI history.
+    pub fn update_token_metadata(
+        env: Env,
+        caller: Address,
+        token_id: u32,
+        uri: String,
+    ) -> Result<u32, ContractError> {
+        let mut metadata = Self::editable_metadata(&env, &caller, token_id)?;
+
+        let count_key = MetadataKey::UriHistoryCount(token_id);
+        let count: u32 = storage::get_persistent(&env, &count_key).unwrap_or(0);
+        storage::set_persistent(
+            &env,
+            &MetadataKey::UriHistory(token_id, count),
+            &metadata.uri,
+        );
+        storage::set_persistent(&env, &count_key, &(count + 1));
+
+        metadata.uri = uri;
+        Self::save_metadata(&env, token_id, &metadata)
+    }
+
+    pub fn update_attributes(
+        env: Env,
+        caller: Address,
+        token_id: u32,
+        attributes: Vec<(String, String)>,
+    ) -> Result<u32, ContractError> {
+        let mut metadata = Self::editable_metadata(&env, &caller, token_id)?;
+        metadata.attributes = attributes;
+        Self::save_metadata(&env, token_id, &metadata)
+    }
+
+    /// Pins the sha256 of the media behind `token_id`'s URI, or unpins it.
+    pub fn set_content_hash(
+        env: Env,
+        caller: Address,
+        token_id: u32,
+        content_hash: Option<BytesN<32>>,
+    ) -> Result<u32, ContractError> {
+        let mut metadata = Self::editable_metadata(&env, &caller, token_id)?;
+        metadata.content_hash = content_hash;
+        Self::save_metadata(&env, token_id, &metadata)
+    }
+
+    /// Whether `hash` matches the content hash pinned for `token_id`.
+    pub fn verify_content(env: Env, token_id: u32, hash: BytesN<32>) -> bool {
+        Self::content_hash(env, token_id) == Some(hash)
+    }
+
+    pub fn content_hash(env: Env, token_id: u32) -> Option<BytesN<32>> {
+        Self::get_token_metadata(env, token_id).and_then(|metadata| metadata.content_hash)
+    }
+
+    /// Number of metadata updates applied to `token_id` since mint.
+    pub fn metadata_version(env: Env, token_id: u32) -> u32 {
+        storage::get_persistent(&env, &MetadataKey::MetadataVersion(token_id)).unwrap_or(0)
+    }
+
+    /// URIs `token_id` had before each URI update, oldest first.
+    pub fn uri_history(env: Env, token_id: u32, start: u32, limit: u32) -> Vec<String> {
+        let count: u32 =
+            storage::get_persistent(&env, &MetadataKey::UriHistoryCount(token_id)).unwrap_or(0);
+        let end = start.saturating_add(limit).min(count);
+
+        let mut uris = Vec::new(&env);
+        for index in start..end {
+            if let Some(uri) =
+                storage::get_persistent(&env, &MetadataKey::UriHistory(token_id, index))
+            {
+                uris.push_back(uri);
+            }
+        }
+        uris
+    }
+
+    /// Trusts or distrusts a marketplace to report sale prices into token
+    /// provenance.
+    pub fn set_trusted_marketplace(env: Env, marketplace: Address, trusted: bool) {
+        Self::require_owner(&env);
+
+        let key = ProvenanceKey::TrustedMarketplace(marketplace);
+        if trusted {
+            storage::set_persistent(&env, &key, &true);
+        } else {
+            storage::remove_persistent(&env, &key);
+        }
+    }
+
+    pub fn is_trusted_marketplace(env: Env, marketplace: Address) -> bool {
+        storage::get_persistent(&env, &ProvenanceKey::TrustedMarketplace(marketplace))
+            .unwrap_or(false)
+    }
+
+    /// Reports that `token_id` is being sold for `price` in `payment_token`.
+    /// The price is logged with the token's next transfer if `marketplace`
+    /// makes it, so it must be reported right before delivery.
+    pub fn report_sale(
+        env: Env,
+        marketplace: Address,
+        token_id: u32,
+        payment_token: Address,
+        price: i128,
+    ) -> Result<(), ContractError> {
+        marketplace.require_auth();
+
+        if !Self::is_trusted_marketplace(env.clone(), marketplace.clone()) {
+            return Err(ContractError::NotAuthorized);
+        }
+        if price < 0 {
+            return Err(ContractError::InvalidAmount);
+        }
+        Self::owner(&env, token_id)?;
+
+        storage::set_persistent(
+            &env,
+            &ProvenanceKey::PendingSale(token_id),
+            &(marketplace, payment_token, price),
+        );
+
+        Ok(())
+    }
+
+    /// Up to `limit` entries of `token_id`'s chain of custody, oldest first,
+    /// starting at `start`.
+    pub fn provenance(env: Env, token_id: u32, start: u32, limit: u32) -> Vec<ProvenanceEntry> {
+        provenance::entries(&env, token_id, start, limit)
+    }
+
+    pub fn provenance_len(env: Env, token_id: u32) -> u32 {
+        provenance::len(&env, token_id)
+    }
+
+    /// Extends the TTL of the collection instance and of the given tokens'
+    /// entries, including their holders' balances and the counts of their
+    /// URI, ownership and provenance histories. The history entries
+    /// themselves are extended by `extend_history_ttl`. Extending TTL only
+    /// costs the caller fees, so the admin or any keeper may call it.
+    pub fn extend_ttl(env: Env, token_ids: Vec<u32>) {
+        storage::extend_instance(&env);
+
+        for token_id in token_ids.iter() {
+            // Reading an index entry extends it too
+            let index: Option<(u32, u32)> =
+                storage::get_persistent(&env, &DataKey::TokenIndex(token_id));
+            if let Some((index, owned_index)) = index {
+                storage::extend_persistent(&env, &DataKey::TokenByIndex(index));
+                if let Some(owner) =
+                    storage::get_persistent::<Address>(&env, &DataKey::Owner(token_id))
+                {
+                    storage::extend_persistent(
+                        &env,
+                        &DataKey::OwnedToken(owner.clone(), owned_index),
+                    );
+                    storage::extend_persistent(&env, &DataKey::Balance(owner));
+                }
+            }
+            storage::extend_persistent(&env, &DataKey::TokenMetadata(token_id));
+            storage::extend_persistent(&env, &DataKey::Approved(token_id));
+            storage::extend_persistent(&env, &DataKey::TokenRoyalty(token_id));
+            storage::extend_persistent(&env, &DataKey::LockedBy(token_id));
+            storage::extend_persistent(&env, &DataKey::User(token_id));
+            storage::extend_persistent(&env, &DataKey::OwnerCheckpointCount(token_id));
+            storage::extend_persistent(&env, &MetadataKey::MetadataVersion(token_id));
+            storage::extend_persistent(&env, &MetadataKey::UriHistoryCount(token_id));
+            storage::extend_persistent(&env, &ProvenanceKey::PendingSale(token_id));
+            storage::extend_persistent(&env, &ProvenanceKey::ProvenanceCount(token_id));
+        }
+    }
+
+    /// Extends the TTL of up to `limit` entries of one of `token_id`'s
+    /// histories, starting at `start`. Burned tokens keep their provenance,
+    /// so it can be extended after the token is gone.
+    pub fn extend_history_ttl(
+        env: Env,
+        token_id: u32,
+        history: TokenHistory,
+        start: u32,
+        limit: u32,
+    ) {
+        let len = match history {
+            TokenHistory::Uri => {
+                storage::get_persistent(&env, &MetadataKey::UriHistoryCount(token_id)).unwrap_or(0)
+            }
+            TokenHistory::Owner => {
+                storage::get_persistent(&env, &DataKey::OwnerCheckpointCount(token_id)).unwrap_or(0)
+            }
+            TokenHistory::Provenance => provenance::len(&env, token_id),
+        };
+
+        for index in start..start.saturating_add(limit).min(len) {
+            match history {
+                TokenHistory::Uri => {
+                    storage::extend_persistent(&env, &MetadataKey::UriHistory(token_id, index))
+                }
+                TokenHistory::Owner => {
+                    storage::extend_persistent(&env, &DataKey::OwnerCheckpoint(token_id, index))
+                }
+                TokenHistory::Provenance => {
+                    storage::extend_persistent(&env, &ProvenanceKey::Provenance(token_id, index))
+                }
+            }
+        }
+    }
+
+    fn owner(env: &Env, token_id: u32) -> Result<Address, ContractError> {
+        storage::get_persistent(env, &DataKey::Owner(token_id)).ok_or(ContractError::NotFound)
+    }
+
+    // Approvals are live up to and including `live_until_ledger`
+    fn is_live(env: &Env, live_until_ledger: u32) -> bool {
+        live_until_ledger >= env.ledger().sequence()
+    }
+
+    fn check_live_until_ledger(env: &Env, live_until_ledger: u32) -> Result<(), ContractError> {
+        if live_until_ledger < env.ledger().sequence()
+            || live_until_ledger > env.ledger().max_live_until_ledger()
+        {
+            return Err(ContractError::InvalidLiveUntilLedger);
+        }
+        Ok(())
+    }
+
+    fn is_operator(env: &Env, owner: &Address, operator: &Address) -> bool {
+        storage::get_persistent::<u32>(
+            env,
+            &DataKey::OperatorApproval(owner.clone(), operator.clone()),
+        )
+        .is_some_and(|live_until_ledger| Self::is_live(env, live_until_ledger))
+    }
+
+    fn set_operator(env: &Env, owner: Address, operator: Address, live_until_ledger: u32) {
+        let key = DataKey::OperatorApproval(owner.clone(), operator.clone());
+        if live_until_ledger == 0 {
+            storage::remove_persistent(env, &key);
+        } else {
+            storage::set_persistent(env, &key, &live_until_ledger);
+        }
+
+        events::emit_approve_for_all(env, owner, operator, live_until_ledger);
+    }
+
+    fn approved(env: &Env, token_id: u32) -> Option<Address> {
+        storage::get_persistent::<TokenApproval>(env, &DataKey::Approved(token_id))
+            .filter(|approval| Self::is_live(env, approval.live_until_ledger))
+            .map(|approval| approval.approved)
+    }
+
+    fn is_approved_or_owner(env: &Env, spender: &Address, owner: &Address, token_id: u32) -> bool {
+        if spender == owner || Self::is_operator(env, owner, spender) {
+            return true;
+        }
+        Self::approved(env, token_id).is_some_and(|approved| approved == *spender)
+    }
+
+    // Under a restrictive policy, only allowlisted operators act on approvals
+    fn check_operator(env: &Env, operator: &Address) -> Result<(), ContractError> {
+        let policy = Self::get_operator_policy(env.clone());
+        if policy.restrict_approvals && !Self::is_allowed_operator(env.clone(), operator.clone()) {
+            return Err(ContractError::OperatorNotAllowed);
+        }
+        Ok(())
+    }
+
+    // Under a restrictive policy, owners can only transfer into or out of an
+    // allowlisted operator, e.g. a marketplace escrow
+    fn check_direct_transfer(env: &Env, from: &Address, to: &Address) -> Result<(), ContractError> {
+        let policy = Self::get_operator_policy(env.clone());
+        if policy.restrict_transfers
+            && !Self::is_allowed_operator(env.clone(), from.clone())
+            && !Self::is_allowed_operator(env.clone(), to.clone())
+        {
+            return Err(ContractError::TransferRestricted);
+        }
+        Ok(())
+    }
+
+    // Asks a contract recipient to acknowledge a token it just received.
+    // Accounts always accept.
+    fn check_receiver(
+        env: &Env,
+        operator: Address,
+        from: Option<Address>,
+        to: Address,
+        token_id: u32,
+        data: Bytes,
+    ) -> Result<(), ContractError> {
+        if !matches!(to.executable(), Some(Executable::Wasm(_))) {
+            return Ok(());
+        }
+
+        let receiver = NftReceiverClient::new(env, &to);
+        match receiver.try_on_nft_received(&operator, &from, &token_id, &data) {
+            Ok(Ok(ack)) if ack == Symbol::new(env, "on_nft_received") => Ok(()),
+            _ => Err(ContractError::ReceiverRejected),
+        }
+    }
+
+    // Ends any rental when a token changes hands
+    fn clear_user(env: &Env, token_id: u32) {
+        let key = DataKey::User(token_id);
+        if storage::has_persistent(env, &key) {
+            storage::remove_persistent(env, &key);
+            events::emit_update_user(env, token_id, None, 0);
+        }
+    }
+
+    fn burn_token(
+        env: &Env,
+        operator: &Address,
+        from: Address,
+        token_id: u32,
+    ) -> Result<(), ContractError> {
+        if Self::is_locked(env.clone(), token_id) {
+            return Err(ContractError::TokenLocked);
+        }
+
+        snapshot::record_owner(env, token_id);
+        storage::remove_persistent(env, &DataKey::Owner(token_id));
+        storage::remove_persistent(env, &DataKey::Approved(token_id));
+        storage::remove_persistent(env, &DataKey::TokenMetadata(token_id));
+        storage::remove_persistent(env, &DataKey::TokenRoyalty(token_id));
+        storage::remove_persistent(env, &DataKey::User(token_id));
+        provenance::record(env, token_id, operator, Some(from.clone()), None);
+
+        enumeration::remove_token(env, &from, token_id);
+
+        storage::extend_instance(env);
+        events::emit_burn(env, from, token_id);
+
+        Ok(())
+    }
+
+    // Moves an owned token and clears its per-token approval. Callers check
+    // ownership and authorization first and emit the transfer event.
+    fn move_token(
+        env: &Env,
+        operator: &Address,
+        from: Address,
+        to: Address,
+        token_id: u32,
+    ) -> Result<(), ContractError> {
+        if Self::is_paused(env) {
+            return Err(ContractError::ContractPaused);
+        }
+        if Self::is_soulbound(env.clone()) {
+            return Err(ContractError::Soulbound);
+        }
+        if Self::is_locked(env.clone(), token_id) {
+            return Err(ContractError::TokenLocked);
+        }
+
+        snapshot::record_owner(env, token_id);
+        storage::set_persistent(env, &DataKey::Owner(token_id), &to);
+        storage::remove_persistent(env, &DataKey::Approved(token_id));
+        Self::clear_user(env, token_id);
+        provenance::record(
+            env,
+            token_id,
+            operator,
+            Some(from.clone()),
+            Some(to.clone()),
+        );
+
+        enumeration::move_token(env, &from, &to, token_id);
+
+        storage::extend_instance(env);
+
+        Ok(())
+    }
+
+    // Records a new token. Callers check minting rights first.
+    fn mint_token(
+        env: &Env,
+        to: Address,
+        token_id: u32,
+        uri: String,
+        attributes: Vec<(String, String)>,
+        creator: Address,
+        content_hash: Option<BytesN<32>>,
+    ) -> Result<(), ContractError> {
+        Self::check_supply(env, 1)?;
+
+        Self::write_token(env, &to, token_id, uri, attributes, creator, content_hash)?;
+        enumeration::add_token(env, &to, token_id);
+
+        storage::extend_instance(env);
+        events::emit_mint(env, to, token_id);
+
+        Ok(())
+    }
+
+    /// Finds the first unreserved id from the auto-increment cursor onwards.
+    /// Every minted id is below the cursor, so only reserved ranges are
+    /// skipped, each at most once.
+    fn next_token_id(env: &Env) -> Result<u32, ContractError> {
+        let reserved = Self::reserved_ids(env.clone());
+        let mut token_id: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::NextTokenId)
+            .unwrap_or(1);
+
+        while let Some((_, end)) = reserved
+            .iter()
+            .find(|(start, end)| u64::from(*start) <= token_id && token_id <= u64::from(*end))
+        {
+            token_id = u64::from(end) + 1;
+        }
+
+        u32::try_from(token_id).map_err(|_| ContractError::SupplyLimitExceeded)
+    }
+
+    // Keeps the auto-increment cursor above every minted id
+    fn advance_next_token_id(env: &Env, token_id: u32) {
+        let next: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::NextTokenId)
+            .unwrap_or(1);
+        if u64::from(token_id) >= next {
+            env.storage()
+                .instance()
+                .set(&DataKey::NextTokenId, &(u64::from(token_id) + 1));
+        }
+    }
+
+    // Checks that `count` more tokens fit under the supply cap
+    fn check_supply(env: &Env, count: u32) -> Result<(), ContractError> {
+        let config: CollectionConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::CollectionConfig)
+            .unwrap();
+        let total_supply: u32 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalSupply)
+            .unwrap_or(0);
+
+        if let Some(max) = config.max_supply
+            && total_supply.saturating_add(count) > max
+        {
+            return Err(ContractError::SupplyLimitExceeded);
+        }
+
+        Ok(())
+    }
+
+    // Stores a new token's metadata and owner; token lists, balances and
+    // supply are left to the caller
+    fn write_token(
+        env: &Env,
+        to: &Address,
+        token_id: u32,
+        uri: String,
+        attributes: Vec<(String, String)>,
+        creator: Address,
+        content_hash: Option<BytesN<32>>,
+    ) -> Result<(), ContractError> {
+        if provenance::exists(env, token_id) {
+            return Err(if Self::is_burned(env.clone(), token_id) {
+                ContractError::TokenBurned
+            } else {
+                ContractError::TokenAlreadyExists
+            });
+        }
+
+        let metadata = TokenMetadata {
+            token_id,
+            uri,
+            attributes,
+            creator,
+            created_at: env.ledger().timestamp(),
+            content_hash,
+        };
+
+        storage::set_persistent(env, &DataKey::TokenMetadata(token_id), &metadata);
+        Self::advance_next_token_id(env, token_id);
+        snapshot::record_owner(env, token_id);
+        storage::set_persistent(env, &DataKey::Owner(token_id), to);
+        provenance::record_mint(env, token_id, &metadata.creator, to.clone());
+
+        Ok(())
+    }
+
+    // Returns the phase running now, or None when no schedule is configured
+    fn active_phase(env: &Env) -> Result<Option<(u32, MintPhase)>, ContractError> {
+        let phases: Vec<MintPhase> = match env.storage().instance().get(&DataKey::MintPhases) {
+            Some(phases) => phases,
+            None => return Ok(None),
+        };
+
+        let now = env.ledger().timestamp();
+        for (index, phase) in phases.iter().enumerate() {
+            if phase.start_time <= now && now < phase.end_time {
+                return Ok(Some((index as u32, phase)));
+            }
+        }
+        Err(ContractError::MintPhaseNotActive)
+    }
+
+    // Charges the mint price, enforces wallet and phase limits and mints the
+    // next free id. Callers check the minter is allowed to mint now.
+    fn paid_mint(
+        env: &Env,
+        minter: Address,
+        phase: Option<(u32, MintPhase)>,
+    ) -> Result<u32, ContractError> {
+        // Phases price themselves, so a free phase runs without a config
+        let mint_config: Option<PublicMintConfig> =
+            env.storage().instance().get(&DataKey::PublicMintConfig);
+        if phase.is_none() && mint_config.is_none() {
+            return Err(ContractError::PublicMintDisabled);
+        }
+        if Self::is_paused(env) {
+            return Err(ContractError::ContractPaused);
+        }
+
+        let count_key = DataKey::MintCount(minter.clone());
+        let minted: u32 = storage::get_persistent(env, &count_key).unwrap_or(0);
+        let wallet_limit = mint_config.as_ref().map_or(0, |config| config.wallet_limit);
+        if wallet_limit > 0 && minted >= wallet_limit {
+            return Err(ContractError::MintLimitExceeded);
+        }
+
+        let mut price = mint_config.as_ref().map_or(0, |config| config.price);
+        if let Some((index, phase)) = &phase {
+            let phase_key = DataKey::PhaseMintCount(*index, minter.clone());
+            let phase_minted: u32 = storage::get_persistent(env, &phase_key).unwrap_or(0);
+            if phase.allocation > 0 && phase_minted >= phase.allocation {
+                return Err(ContractError::MintLimitExceeded);
+            }
+            storage::set_persistent(env, &phase_key, &(phase_minted + 1));
+            price = phase.price;
+        }
+
+        // The collection's creator is credited with the work, not the buyer.
+        // Public mints have no per-token URI; token_uri derives one
+        let creator = Self::get_owner(env.clone());
+        let token_id = Self::next_token_id(env)?;
+        Self::mint_token(
+            env,
+            minter.clone(),
+            token_id,
+            String::from_str(env, ""),
+            Vec::new(env),
+            creator,
+            None,
+        )?;
+        storage::set_persistent(env, &count_key, &(minted + 1));
+
+        if price > 0 {
+            let mint_config = mint_config.ok_or(ContractError::PublicMintDisabled)?;
+            let payment = token::Client::new(env, &mint_config.payment_token);
+            if payment
+                .try_transfer(&minter, &mint_config.payout_address, &price)
+                .is_err()
+            {
+                return Err(ContractError::InsufficientBalance);
+            }
+        }
+
+        Ok(token_id)
+    }
+
+    fn check_royalty(royalty: &RoyaltyInfo) -> Result<(), ContractError> {
+        if royalty.percentage > 10000 {
+            return Err(ContractError::InvalidRoyalty);
+        }
+        if !royalty.splits.is_empty() {
+            let total = royalty
+                .splits
+                .iter()
+                .try_fold(0u32, |total, split| total.checked_add(split.share));
+            if total != Some(10000) {
+                return Err(ContractError::InvalidRoyalty);
+            }
+        }
+        Ok(())
+    }
+
+    fn royalty_amount(royalty: &RoyaltyInfo, sale_price: i128) -> Result<i128, ContractError> {
+        if sale_price < 0 {
+            return Err(ContractError::InvalidAmount);
+        }
+        sale_price
+            .checked_mul(royalty.percentage as i128)
+            .map(|scaled| scaled / 10000)
+            .ok_or(ContractError::InvalidAmount)
+    }
+
+    fn is_paused(env: &Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::IsPaused)
+            .unwrap_or(false)
+    }
+
+    // Loads a token's metadata for `caller` to edit
+    fn editable_metadata(
+        env: &Env,
+        caller: &Address,
+        token_id: u32,
+    ) -> Result<TokenMetadata, ContractError> {
+        Self::require_role(env, caller, Role::MetadataAdmin)?;
+        if Self::is_metadata_frozen(env.clone()) {
+            return Err(ContractError::MetadataFrozen);
+        }
+
+        storage::get_persistent(env, &DataKey::TokenMetadata(token_id))
+            .ok_or(ContractError::NotFound)
+    }
+
+    // Stores edited metadata and returns the token's new metadata version
+    fn save_metadata(
+        env: &Env,
+        token_id: u32,
+        metadata: &TokenMetadata,
+    ) -> Result<u32, ContractError> {
+        storage::set_persistent(env, &DataKey::TokenMetadata(token_id), metadata);
+
+        let version_key = MetadataKey::MetadataVersion(token_id);
+        let version = storage::get_persistent::<u32>(env, &version_key).unwrap_or(0) + 1;
+        storage::set_persistent(env, &version_key, &version);
+
+        events::emit_metadata_update(env, token_id);
+
+        Ok(version)
+    }
+
+    fn require_owner(env: &Env) -> Address {
+        let owner = Self::get_owner(env.clone());
+        owner.require_auth();
+        owner
+    }
+
+    /// Checks that `minter` authorized minting `count` more tokens.
+    fn check_minter(env: &Env, minter: &Address, count: u32) -> Result<(), ContractError> {
+        minter.require_auth();
+        Self::count_mints(env, minter, count)
+    }
+
+    /// Checks that `minter` may mint `count` more tokens and counts them
+    /// against its quota.
+    fn count_mints(env: &Env, minter: &Address, count: u32) -> Result<(), ContractError> {
+        if !Self::has_role(env.clone(), Role::Minter, minter.clone()) {
+            return Err(ContractError::NotMinter);
+        }
+
+        let limits = Self::minter_limits(env.clone(), minter.clone());
+        if limits.expires != 0 && env.ledger().timestamp() > limits.expires {
+            return Err(ContractError::MinterExpired);
+        }
+        let minted = Self::minted_by(env.clone(), minter.clone());
+        if limits.quota != 0 && minted.saturating_add(count) > limits.quota {
+            return Err(ContractError::MintLimitExceeded);
+        }
+        storage::set_persistent(
+            env,
+            &DataKey::MinterMintCount(minter.clone()),
+            &(minted + count),
+        );
+
+        Ok(())
+    }
+
+    fn require_role(env: &Env, caller: &Address, role: Role) -> Result<(), ContractError> {
+        caller.require_auth();
+        if !Self::has_role(env.clone(), role, caller.clone()) {
+            return Err(ContractError::NotAuthorized);
+        }
+        Ok(())
+    }
+
+    fn grant(env: &Env, role: Role, account: Address, sender: Address) {
+        let key = DataKey::Role(role, account.clone());
+        if !storage::get_persistent(env, &key).unwrap_or(false) {
+            storage::set_persistent(env, &key, &true);
+            events::emit_role_granted(env, role, account, sender);
+        }
+    }
+
+    fn revoke(env: &Env, role: Role, account: Address, sender: Address) {
+        let key = DataKey::Role(role, account.clone());
+        if storage::has_persistent(env, &key) {
+            storage::remove_persistent(env, &key);
+            events::emit_role_revoked(env, role, account, sender);
+        }
+    }
+}
+
+#[contractimpl]
+impl NonFungibleToken for NftCollection {
+    fn balance(env: Env, owner: Address) -> u32 {
+        storage::get_persistent(&env, &DataKey::Balance(owner)).unwrap_or(0)
+    }
+
+    fn owner_of(env: Env, token_id: u32) -> Result<Address, ContractError> {
+        Self::owner(&env, token_id)
+    }
+
+    fn transfer(env: Env, from: Address, to: Address, token_id: u32) -> Result<(), ContractError> {
+        from.require_auth();
+
+        if Self::owner(&env, token_id)? != from {
+            return Err(ContractError::NotAuthorized);
+        }
+        Self::check_direct_transfer(&env, &from, &to)?;
+
+        Self::move_token(&env, &from, from.clone(), to.clone(), token_id)?;
+        events::emit_transfer(&env, from, to, token_id);
+
+        Ok(())
+    }
+
+    fn transfer_from(
+        env: Env,
+        spender: Address,
+        from: Address,
+        to: Address,
+        token_id: u32,
+    ) -> Result<(), ContractError> {
+        spender.require_auth();
+
+        let owner = Self::owner(&env, token_id)?;
+        if owner != from {
+            return Err(ContractError::NotAuthorized);
+        }
+        if !Self::is_approved_or_owner(&env, &spender, &owner, token_id) {
+            return Err(ContractError::NotApproved);
+        }
+        if spender != owner {
+            Self::check_operator(&env, &spender)?;
+        }
+        // Only allowed operators are exempt from transfer restrictions
+        if !Self::is_allowed_operator(env.clone(), spender.clone()) {
+            Self::check_direct_transfer(&env, &from, &to)?;
+        }
+
+        Self::move_token(&env, &spender, from.clone(), to.clone(), token_id)?;
+        events::emit_transfer(&env, from, to, token_id);
+
+        Ok(())
+    }
+
+    fn approve(
+        env: Env,
+        approver: Address,
+        approved: Address,
+        token_id: u32,
+        live_until_ledger: u32,
+    ) -> Result<(), ContractError> {
+        approver.require_auth();
+
+        let owner = Self::owner(&env, token_id)?;
+        // The owner or one of its operators may manage per-token approvals
+        if approver != owner && !Self::is_operator(&env, &owner, &approver) {
+            return Err(ContractError::NotAuthorized);
+        }
+
+        if live_until_ledger == 0 {
+            storage::remove_persistent(&env, &DataKey::Approved(token_id));
+        } else {
+            if Self::is_soulbound(env.clone()) {
+                return Err(ContractError::Soulbound);
+            }
+            Self::check_live_until_ledger(&env, live_until_ledger)?;
+            Self::check_operator(&env, &approved)?;
+            if approved == owner {
+                return Err(ContractError::InvalidRecipient);
+            }
+            storage::set_persistent(
+                &env,
+                &DataKey::Approved(token_id),
+                &TokenApproval {
+                    approved: approved.clone(),
+                    live_until_ledger,
+                },
+            );
+        }
+
+        events::emit_approve(&env, approver, approved, token_id, live_until_ledger);
+
+        Ok(())
+    }
+
+    fn approve_for_all(
+        env: Env,
+        owner: Address,
+        operator: Address,
+        live_until_ledger: u32,
+    ) -> Result<(), ContractError> {
+        owner.require_auth();
+
+        if owner == operator {
+            return Err(ContractError::InvalidRecipient);
+        }
+        if live_until_ledger != 0 {
+            if Self::is_soulbound(env.clone()) {
+                return Err(ContractError::Soulbound);
+            }
+            Self::check_live_until_ledger(&env, live_until_ledger)?;
+            Self::check_operator(&env, &operator)?;
+        }
+
+        Self::set_operator(&env, owner, operator, live_until_ledger);
+
+        Ok(())
+    }
+
+    fn get_approved(env: Env, token_id: u32) -> Option<Address> {
+        Self::approved(&env, token_id)
+    }
+
+    fn is_approved_for_all(env: Env, owner: Address, operator: Address) -> bool {
+        Self::is_operator(&env, &owner, &operator)
+    }
+
+    fn name(env: Env) -> String {
+        let config: CollectionConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::CollectionConfig)
+            .unwrap();
+        config.name
+    }
+
+    fn symbol(env: Env) -> String {
+        let config: CollectionConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::CollectionConfig)
+            .unwrap();
+        config.symbol
+    }
+
+    fn token_uri(env: Env, token_id: u32) -> Result<String, ContractError> {
+        let metadata: TokenMetadata =
+            storage::get_persistent(&env, &DataKey::TokenMetadata(token_id))
+                .ok_or(ContractError::NotFound)?;
+        let config: CollectionConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::CollectionConfig)
+            .unwrap();
+        // Every token shows the placeholder until reveal, so per-token URIs
+        // can't leak the reveal early
+        if let Some(placeholder) = config.placeholder_uri
+            && !Self::is_revealed(env.clone())
+        {
+            return Ok(placeholder);
+        }
+
+        if Self::is_onchain_metadata(env.clone()) {
+            Ok(uri::json_data_uri(
+                &env,
+                &config.name,
+                &config.description,
+                token_id,
+                &metadata.attributes,
+                Self::svg_template(env.clone()),
+            ))
+        } else if !metadata.uri.is_empty() {
+            Ok(metadata.uri)
+        } else {
+            Ok(uri::join(&config.base_uri, token_id))
+        }
     }
 }
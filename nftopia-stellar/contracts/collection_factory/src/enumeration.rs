@@ -0,0 +1,93 @@
+use crate::snapshot;
+use crate::storage::{self, DataKey};
+use soroban_sdk::{Address, Env};
+
+// Token lists are kept one id per storage entry, with swap-and-pop removal,
+// so every entry stays the same size however many tokens exist. The
+// collection list is as long as `TotalSupply`, an owner's as their `Balance`.
+// A token's positions in both lists share one `TokenIndex` entry.
+
+/// Appends `token_id` to the collection's token list and to `owner`'s.
+pub fn add_token(env: &Env, owner: &Address, token_id: u32) {
+    let supply: u32 = env
+        .storage()
+        .instance()
+        .get(&DataKey::TotalSupply)
+        .unwrap_or(0);
+    let owned_index = push_owned(env, owner, token_id);
+
+    storage::set_persistent(env, &DataKey::TokenByIndex(supply), &token_id);
+    storage::set_persistent(env, &DataKey::TokenIndex(token_id), &(supply, owned_index));
+    env.storage()
+        .instance()
+        .set(&DataKey::TotalSupply, &(supply + 1));
+}
+
+/// Removes `token_id` from the collection's token list and from `owner`'s.
+pub fn remove_token(env: &Env, owner: &Address, token_id: u32) {
+    let supply: u32 = env
+        .storage()
+        .instance()
+        .get(&DataKey::TotalSupply)
+        .unwrap_or(0);
+    let (index, owned_index): (u32, u32) =
+        storage::get_persistent(env, &DataKey::TokenIndex(token_id)).unwrap();
+    pop_owned(env, owner, owned_index);
+
+    let last = supply - 1;
+    if index != last {
+        let moved: u32 = storage::get_persistent(env, &DataKey::TokenByIndex(last)).unwrap();
+        storage::set_persistent(env, &DataKey::TokenByIndex(index), &moved);
+        let moved_key = DataKey::TokenIndex(moved);
+        let (_, moved_owned_index): (u32, u32) = storage::get_persistent(env, &moved_key).unwrap();
+        storage::set_persistent(env, &moved_key, &(index, moved_owned_index));
+    }
+    storage::remove_persistent(env, &DataKey::TokenByIndex(last));
+    storage::remove_persistent(env, &DataKey::TokenIndex(token_id));
+    env.storage().instance().set(&DataKey::TotalSupply, &last);
+}
+
+/// Moves `token_id` from `from`'s token list to `to`'s.
+pub fn move_token(env: &Env, from: &Address, to: &Address, token_id: u32) {
+    let key = DataKey::TokenIndex(token_id);
+    let (index, owned_index): (u32, u32) = storage::get_persistent(env, &key).unwrap();
+    pop_owned(env, from, owned_index);
+    let owned_index = push_owned(env, to, token_id);
+    storage::set_persistent(env, &key, &(index, owned_index));
+}
+
+// Appends `token_id` to `owner`'s list, bumps their balance and returns the
+// token's position in the list
+fn push_owned(env: &Env, owner: &Address, token_id: u32) -> u32 {
+    let balance_key = DataKey::Balance(owner.clone());
+    let balance: u32 = storage::get_persistent(env, &balance_key).unwrap_or(0);
+    snapshot::record_balance(env, owner, balance);
+
+    storage::set_persistent(env, &DataKey::OwnedToken(owner.clone(), balance), &token_id);
+    storage::set_persistent(env, &balance_key, &(balance + 1));
+    balance
+}
+
+// Removes the token at `index` from `owner`'s list and lowers their balance
+fn pop_owned(env: &Env, owner: &Address, index: u32) {
+    let balance_key = DataKey::Balance(owner.clone());
+    let balance: u32 = storage::get_persistent(env, &balance_key).unwrap_or(0);
+    snapshot::record_balance(env, owner, balance);
+
+    let last = balance - 1;
+    if index != last {
+        let moved: u32 =
+            storage::get_persistent(env, &DataKey::OwnedToken(owner.clone(), last)).unwrap();
+        storage::set_persistent(env, &DataKey::OwnedToken(owner.clone(), index), &moved);
+        let moved_key = DataKey::TokenIndex(moved);
+        let (moved_index, _): (u32, u32) = storage::get_persistent(env, &moved_key).unwrap();
+        storage::set_persistent(env, &moved_key, &(moved_index, index));
+    }
+    storage::remove_persistent(env, &DataKey::OwnedToken(owner.clone(), last));
+
+    if last == 0 {
+        storage::remove_persistent(env, &balance_key);
+    } else {
+        storage::set_persistent(env, &balance_key, &last);
+    }
+}
@@ -16,4 +16,27 @@ pub enum ContractError {
     InvalidRoyalty = 10,
     InvalidRecipient = 11,
     TokenAlreadyExists = 12,
+    NotApproved = 13,
+    InvalidLiveUntilLedger = 14,
+    PublicMintDisabled = 15,
+    MintLimitExceeded = 16,
+    MintPhaseNotActive = 17,
+    NotAllowlisted = 18,
+    InvalidMintPhase = 19,
+    VoucherExpired = 20,
+    VoucherAlreadyUsed = 21,
+    AlreadyRevealed = 22,
+    NotRevealed = 23,
+    MetadataFrozen = 24,
+    NoPendingOwner = 25,
+    Soulbound = 26,
+    ReceiverRejected = 27,
+    OperatorNotAllowed = 28,
+    TransferRestricted = 29,
+    TokenLocked = 30,
+    InvalidSnapshot = 31,
+    MinterExpired = 32,
+    TokenBurned = 33,
+    InvalidIdRange = 34,
+    TooManyReservedRanges = 35,
 }
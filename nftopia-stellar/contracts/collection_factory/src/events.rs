@@ -1,4 +1,5 @@
-use soroban_sdk::{Address, Env, contractevent};
+use crate::types::Role;
+use soroban_sdk::{Address, BytesN, Env, String, Vec, contractevent};
 
 #[contractevent]
 #[derive(Clone, Debug)]
@@ -8,32 +9,192 @@ pub struct Created {
     pub id: u32,
 }
 
-#[contractevent]
+// Token events follow the Stellar non-fungible token interface (SEP-0050):
+// the collection is the emitting contract, addresses are topics and the
+// token id is the event data.
+
+#[contractevent(data_format = "single-value")]
 #[derive(Clone, Debug)]
 pub struct Mint {
-    pub collection: Address,
+    #[topic]
     pub to: Address,
     pub token_id: u32,
-    pub amount: u32,
 }
 
-#[contractevent]
+#[contractevent(data_format = "single-value")]
 #[derive(Clone, Debug)]
 pub struct Transfer {
-    pub collection: Address,
+    #[topic]
     pub from: Address,
+    #[topic]
     pub to: Address,
     pub token_id: u32,
-    pub amount: u32,
 }
 
-#[contractevent]
+#[contractevent(data_format = "single-value")]
 #[derive(Clone, Debug)]
 pub struct Burn {
-    pub collection: Address,
+    #[topic]
     pub from: Address,
     pub token_id: u32,
-    pub amount: u32,
+}
+
+// Batch operations emit one event for the whole batch instead of one per token
+
+#[contractevent]
+#[derive(Clone, Debug)]
+pub struct BatchMint {
+    pub to: Vec<Address>,
+    pub token_ids: Vec<u32>,
+}
+
+#[contractevent(data_format = "single-value")]
+#[derive(Clone, Debug)]
+pub struct BatchTransfer {
+    #[topic]
+    pub from: Address,
+    #[topic]
+    pub to: Address,
+    pub token_ids: Vec<u32>,
+}
+
+#[contractevent(data_format = "vec")]
+#[derive(Clone, Debug)]
+pub struct Approve {
+    #[topic]
+    pub approver: Address,
+    #[topic]
+    pub token_id: u32,
+    pub approved: Address,
+    pub live_until_ledger: u32,
+}
+
+#[contractevent(data_format = "vec")]
+#[derive(Clone, Debug)]
+pub struct ApproveForAll {
+    #[topic]
+    pub owner: Address,
+    pub operator: Address,
+    pub live_until_ledger: u32,
+}
+
+#[contractevent(data_format = "single-value")]
+#[derive(Clone, Debug)]
+pub struct VoucherCancelled {
+    #[topic]
+    pub signer: BytesN<32>,
+    pub nonce: u64,
+}
+
+// Rental user changes, as in ERC-4907. A `None` user clears the rental.
+#[contractevent(data_format = "vec")]
+#[derive(Clone, Debug)]
+pub struct UpdateUser {
+    #[topic]
+    pub token_id: u32,
+    pub user: Option<Address>,
+    pub expires: u64,
+}
+
+#[contractevent(data_format = "single-value")]
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    #[topic]
+    pub id: u32,
+    pub timestamp: u64,
+}
+
+#[contractevent(data_format = "single-value")]
+#[derive(Clone, Debug)]
+pub struct Lock {
+    #[topic]
+    pub locker: Address,
+    pub token_id: u32,
+}
+
+#[contractevent(data_format = "single-value")]
+#[derive(Clone, Debug)]
+pub struct Unlock {
+    #[topic]
+    pub locker: Address,
+    pub token_id: u32,
+}
+
+#[contractevent(data_format = "single-value")]
+#[derive(Clone, Debug)]
+pub struct OperatorAllowed {
+    #[topic]
+    pub operator: Address,
+    pub allowed: bool,
+}
+
+#[contractevent(data_format = "single-value")]
+#[derive(Clone, Debug)]
+pub struct ProvenanceCommitted {
+    pub hash: BytesN<32>,
+}
+
+#[contractevent(data_format = "single-value")]
+#[derive(Clone, Debug)]
+pub struct Reveal {
+    pub base_uri: String,
+}
+
+#[contractevent(data_format = "single-value")]
+#[derive(Clone, Debug)]
+pub struct MetadataFrozen {
+    pub base_uri: String,
+}
+
+// Refresh hints for indexers, as in EIP-4906
+
+#[contractevent(data_format = "single-value")]
+#[derive(Clone, Debug)]
+pub struct MetadataUpdate {
+    pub token_id: u32,
+}
+
+#[contractevent(data_format = "vec")]
+#[derive(Clone, Debug)]
+pub struct BatchMetadataUpdate {
+    pub from_token_id: u32,
+    pub to_token_id: u32,
+}
+
+#[contractevent(data_format = "single-value")]
+#[derive(Clone, Debug)]
+pub struct RoleGranted {
+    #[topic]
+    pub role: Role,
+    #[topic]
+    pub account: Address,
+    pub sender: Address,
+}
+
+#[contractevent(data_format = "single-value")]
+#[derive(Clone, Debug)]
+pub struct RoleRevoked {
+    #[topic]
+    pub role: Role,
+    #[topic]
+    pub account: Address,
+    pub sender: Address,
+}
+
+#[contractevent(data_format = "single-value")]
+#[derive(Clone, Debug)]
+pub struct OwnershipTransferStarted {
+    #[topic]
+    pub owner: Address,
+    pub pending_owner: Address,
+}
+
+#[contractevent(data_format = "single-value")]
+#[derive(Clone, Debug)]
+pub struct OwnershipTransferred {
+    #[topic]
+    pub previous_owner: Address,
+    pub new_owner: Address,
 }
 
 pub fn emit_collection_created(
@@ -50,40 +211,139 @@ pub fn emit_collection_created(
     .publish(env);
 }
 
-pub fn emit_mint(env: &Env, collection: Address, to: Address, token_id: u32, amount: u32) {
-    Mint {
-        collection,
+pub fn emit_mint(env: &Env, to: Address, token_id: u32) {
+    Mint { to, token_id }.publish(env);
+}
+
+pub fn emit_transfer(env: &Env, from: Address, to: Address, token_id: u32) {
+    Transfer { from, to, token_id }.publish(env);
+}
+
+pub fn emit_burn(env: &Env, from: Address, token_id: u32) {
+    Burn { from, token_id }.publish(env);
+}
+
+pub fn emit_batch_mint(env: &Env, to: Vec<Address>, token_ids: Vec<u32>) {
+    BatchMint { to, token_ids }.publish(env);
+}
+
+pub fn emit_batch_transfer(env: &Env, from: Address, to: Address, token_ids: Vec<u32>) {
+    BatchTransfer {
+        from,
         to,
-        token_id,
-        amount,
+        token_ids,
     }
     .publish(env);
 }
 
-pub fn emit_transfer(
+pub fn emit_approve(
     env: &Env,
-    collection: Address,
-    from: Address,
-    to: Address,
+    approver: Address,
+    approved: Address,
     token_id: u32,
-    amount: u32,
+    live_until_ledger: u32,
 ) {
-    Transfer {
-        collection,
-        from,
-        to,
+    Approve {
+        approver,
         token_id,
-        amount,
+        approved,
+        live_until_ledger,
     }
     .publish(env);
 }
 
-pub fn emit_burn(env: &Env, collection: Address, from: Address, token_id: u32, amount: u32) {
-    Burn {
-        collection,
-        from,
+pub fn emit_approve_for_all(env: &Env, owner: Address, operator: Address, live_until_ledger: u32) {
+    ApproveForAll {
+        owner,
+        operator,
+        live_until_ledger,
+    }
+    .publish(env);
+}
+
+pub fn emit_voucher_cancelled(env: &Env, signer: BytesN<32>, nonce: u64) {
+    VoucherCancelled { signer, nonce }.publish(env);
+}
+
+pub fn emit_update_user(env: &Env, token_id: u32, user: Option<Address>, expires: u64) {
+    UpdateUser {
         token_id,
-        amount,
+        user,
+        expires,
+    }
+    .publish(env);
+}
+
+pub fn emit_snapshot(env: &Env, id: u32, timestamp: u64) {
+    Snapshot { id, timestamp }.publish(env);
+}
+
+pub fn emit_lock(env: &Env, locker: Address, token_id: u32) {
+    Lock { locker, token_id }.publish(env);
+}
+
+pub fn emit_unlock(env: &Env, locker: Address, token_id: u32) {
+    Unlock { locker, token_id }.publish(env);
+}
+
+pub fn emit_operator_allowed(env: &Env, operator: Address, allowed: bool) {
+    OperatorAllowed { operator, allowed }.publish(env);
+}
+
+pub fn emit_provenance_committed(env: &Env, hash: BytesN<32>) {
+    ProvenanceCommitted { hash }.publish(env);
+}
+
+pub fn emit_reveal(env: &Env, base_uri: String) {
+    Reveal { base_uri }.publish(env);
+}
+
+pub fn emit_metadata_frozen(env: &Env, base_uri: String) {
+    MetadataFrozen { base_uri }.publish(env);
+}
+
+pub fn emit_metadata_update(env: &Env, token_id: u32) {
+    MetadataUpdate { token_id }.publish(env);
+}
+
+pub fn emit_batch_metadata_update(env: &Env, from_token_id: u32, to_token_id: u32) {
+    BatchMetadataUpdate {
+        from_token_id,
+        to_token_id,
+    }
+    .publish(env);
+}
+
+pub fn emit_role_granted(env: &Env, role: Role, account: Address, sender: Address) {
+    RoleGranted {
+        role,
+        account,
+        sender,
+    }
+    .publish(env);
+}
+
+pub fn emit_role_revoked(env: &Env, role: Role, account: Address, sender: Address) {
+    RoleRevoked {
+        role,
+        account,
+        sender,
+    }
+    .publish(env);
+}
+
+pub fn emit_ownership_transfer_started(env: &Env, owner: Address, pending_owner: Address) {
+    OwnershipTransferStarted {
+        owner,
+        pending_owner,
+    }
+    .publish(env);
+}
+
+pub fn emit_ownership_transferred(env: &Env, previous_owner: Address, new_owner: Address) {
+    OwnershipTransferred {
+        previous_owner,
+        new_owner,
     }
     .publish(env);
 }
@@ -0,0 +1,70 @@
+use crate::error::ContractError;
+use soroban_sdk::{Address, Bytes, Env, String, Symbol, contractclient};
+
+/// Stellar non-fungible token interface (SEP-0050).
+///
+/// Wallets and indexers only rely on these entrypoints and the matching
+/// events in `events.rs`. Collection specific functionality is exposed
+/// separately by `NftCollection`.
+pub trait NonFungibleToken {
+    /// Number of tokens held by `owner`.
+    fn balance(env: Env, owner: Address) -> u32;
+
+    fn owner_of(env: Env, token_id: u32) -> Result<Address, ContractError>;
+
+    fn transfer(env: Env, from: Address, to: Address, token_id: u32) -> Result<(), ContractError>;
+
+    fn transfer_from(
+        env: Env,
+        spender: Address,
+        from: Address,
+        to: Address,
+        token_id: u32,
+    ) -> Result<(), ContractError>;
+
+    /// Approves `approved` to move `token_id` until `live_until_ledger`.
+    /// A `live_until_ledger` of 0 revokes the approval.
+    fn approve(
+        env: Env,
+        approver: Address,
+        approved: Address,
+        token_id: u32,
+        live_until_ledger: u32,
+    ) -> Result<(), ContractError>;
+
+    /// Approves `operator` to manage all of `owner`'s tokens until
+    /// `live_until_ledger`. A `live_until_ledger` of 0 revokes the approval.
+    fn approve_for_all(
+        env: Env,
+        owner: Address,
+        operator: Address,
+        live_until_ledger: u32,
+    ) -> Result<(), ContractError>;
+
+    fn get_approved(env: Env, token_id: u32) -> Option<Address>;
+
+    fn is_approved_for_all(env: Env, owner: Address, operator: Address) -> bool;
+
+    fn name(env: Env) -> String;
+
+    fn symbol(env: Env) -> String;
+
+    fn token_uri(env: Env, token_id: u32) -> Result<String, ContractError>;
+}
+
+/// Callback invoked on contract recipients of `safe_transfer`,
+/// `safe_transfer_from` and `safe_mint`.
+///
+/// Implementations accept the token by returning the `on_nft_received`
+/// symbol; anything else, including a failed call, reverts the transfer.
+/// `from` is `None` for mints.
+#[contractclient(name = "NftReceiverClient")]
+pub trait NftReceiver {
+    fn on_nft_received(
+        env: Env,
+        operator: Address,
+        from: Option<Address>,
+        token_id: u32,
+        data: Bytes,
+    ) -> Symbol;
+}
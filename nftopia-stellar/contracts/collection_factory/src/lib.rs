@@ -1,10 +1,16 @@
 #![no_std]
+pub mod allowlist;
 pub mod collection;
+pub mod enumeration;
 pub mod error;
 pub mod events;
 pub mod factory;
+pub mod interface;
+pub mod provenance;
+pub mod snapshot;
 pub mod storage;
 pub mod types;
+pub mod uri;
 
 pub use crate::collection::NftCollection;
 pub use crate::factory::CollectionFactory;
@@ -0,0 +1,79 @@
+use crate::storage::{self, ProvenanceKey};
+use crate::types::ProvenanceEntry;
+use soroban_sdk::{Address, Env, Vec};
+
+// The log is append-only. Each custody change is its own entry under the
+// token's next index, so entries stay the same size however long a token's
+// history grows, and burned tokens keep theirs. A mint only writes entry 0;
+// the count is stored once the token moves.
+
+/// Starts `token_id`'s log with its mint by `operator` to `to`.
+pub fn record_mint(env: &Env, token_id: u32, operator: &Address, to: Address) {
+    let entry = ProvenanceEntry {
+        operator: operator.clone(),
+        from: None,
+        to: Some(to),
+        timestamp: env.ledger().timestamp(),
+        price: None,
+        payment_token: None,
+    };
+    storage::set_persistent(env, &ProvenanceKey::Provenance(token_id, 0), &entry);
+}
+
+/// Whether `token_id` was ever minted. Burned tokens keep their log.
+pub fn exists(env: &Env, token_id: u32) -> bool {
+    storage::has_persistent(env, &ProvenanceKey::Provenance(token_id, 0))
+}
+
+/// Appends a transfer or burn of `token_id` made by `operator`. A sale price
+/// reported by `operator` beforehand is logged with it.
+pub fn record(
+    env: &Env,
+    token_id: u32,
+    operator: &Address,
+    from: Option<Address>,
+    to: Option<Address>,
+) {
+    let pending_key = ProvenanceKey::PendingSale(token_id);
+    let sale = match storage::get_persistent::<(Address, Address, i128)>(env, &pending_key) {
+        Some((marketplace, payment_token, price)) => {
+            storage::remove_persistent(env, &pending_key);
+            (marketplace == *operator).then_some((payment_token, price))
+        }
+        None => None,
+    };
+
+    let count_key = ProvenanceKey::ProvenanceCount(token_id);
+    let count: u32 = storage::get_persistent(env, &count_key).unwrap_or(1);
+    let entry = ProvenanceEntry {
+        operator: operator.clone(),
+        from,
+        to,
+        timestamp: env.ledger().timestamp(),
+        price: sale.as_ref().map(|(_, price)| *price),
+        payment_token: sale.map(|(payment_token, _)| payment_token),
+    };
+    storage::set_persistent(env, &ProvenanceKey::Provenance(token_id, count), &entry);
+    storage::set_persistent(env, &count_key, &(count + 1));
+}
+
+/// Number of entries in `token_id`'s log.
+pub fn len(env: &Env, token_id: u32) -> u32 {
+    storage::get_persistent(env, &ProvenanceKey::ProvenanceCount(token_id))
+        .unwrap_or_else(|| exists(env, token_id) as u32)
+}
+
+/// Up to `limit` entries of `token_id`'s log, oldest first, from `start`.
+pub fn entries(env: &Env, token_id: u32, start: u32, limit: u32) -> Vec<ProvenanceEntry> {
+    let end = start.saturating_add(limit).min(len(env, token_id));
+
+    let mut entries = Vec::new(env);
+    for index in start..end {
+        if let Some(entry) =
+            storage::get_persistent(env, &ProvenanceKey::Provenance(token_id, index))
+        {
+            entries.push_back(entry);
+        }
+    }
+    entries
+}
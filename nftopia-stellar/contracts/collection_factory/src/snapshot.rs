@@ -0,0 +1,129 @@
+use crate::error::ContractError;
+use crate::storage::{self, DataKey};
+use soroban_sdk::{Address, Env};
+
+// Checkpoints are written lazily: the first time a token's owner or an
+// address's balance changes after a snapshot, the value it had at that
+// snapshot is stored under the next checkpoint index. Each checkpoint is its
+// own entry, so entries stay the same size however often tokens move.
+
+/// Id of the latest snapshot, 0 before the first one.
+pub fn current(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::CurrentSnapshot)
+        .unwrap_or(0)
+}
+
+/// Records `token_id`'s owner before it changes.
+pub fn record_owner(env: &Env, token_id: u32) {
+    let snapshot_id = current(env);
+    if snapshot_id == 0 {
+        return;
+    }
+
+    let count_key = DataKey::OwnerCheckpointCount(token_id);
+    let count: u32 = storage::get_persistent(env, &count_key).unwrap_or(0);
+    if count > 0 {
+        let (last_id, _): (u32, Option<Address>) =
+            storage::get_persistent(env, &DataKey::OwnerCheckpoint(token_id, count - 1)).unwrap();
+        if last_id == snapshot_id {
+            return;
+        }
+    }
+
+    let owner: Option<Address> = storage::get_persistent(env, &DataKey::Owner(token_id));
+    storage::set_persistent(
+        env,
+        &DataKey::OwnerCheckpoint(token_id, count),
+        &(snapshot_id, owner),
+    );
+    storage::set_persistent(env, &count_key, &(count + 1));
+}
+
+/// Records `owner`'s balance before it changes.
+pub fn record_balance(env: &Env, owner: &Address, balance: u32) {
+    let snapshot_id = current(env);
+    if snapshot_id == 0 {
+        return;
+    }
+
+    let count_key = DataKey::BalanceCheckpointCount(owner.clone());
+    let count: u32 = storage::get_persistent(env, &count_key).unwrap_or(0);
+    if count > 0 {
+        let (last_id, _): (u32, u32) =
+            storage::get_persistent(env, &DataKey::BalanceCheckpoint(owner.clone(), count - 1))
+                .unwrap();
+        if last_id == snapshot_id {
+            return;
+        }
+    }
+
+    storage::set_persistent(
+        env,
+        &DataKey::BalanceCheckpoint(owner.clone(), count),
+        &(snapshot_id, balance),
+    );
+    storage::set_persistent(env, &count_key, &(count + 1));
+}
+
+/// `token_id`'s owner at `snapshot_id`, or `None` when it hasn't changed
+/// since. An inner `None` means the token didn't exist then.
+pub fn owner_at(
+    env: &Env,
+    token_id: u32,
+    snapshot_id: u32,
+) -> Result<Option<Option<Address>>, ContractError> {
+    check_id(env, snapshot_id)?;
+
+    let count: u32 =
+        storage::get_persistent(env, &DataKey::OwnerCheckpointCount(token_id)).unwrap_or(0);
+    Ok(search(count, snapshot_id, |index| {
+        storage::get_persistent::<(u32, Option<Address>)>(
+            env,
+            &DataKey::OwnerCheckpoint(token_id, index),
+        )
+        .unwrap()
+    }))
+}
+
+/// `owner`'s balance at `snapshot_id`, or `None` when it hasn't changed
+/// since.
+pub fn balance_at(
+    env: &Env,
+    owner: &Address,
+    snapshot_id: u32,
+) -> Result<Option<u32>, ContractError> {
+    check_id(env, snapshot_id)?;
+
+    let count: u32 =
+        storage::get_persistent(env, &DataKey::BalanceCheckpointCount(owner.clone())).unwrap_or(0);
+    Ok(search(count, snapshot_id, |index| {
+        storage::get_persistent::<(u32, u32)>(
+            env,
+            &DataKey::BalanceCheckpoint(owner.clone(), index),
+        )
+        .unwrap()
+    }))
+}
+
+fn check_id(env: &Env, snapshot_id: u32) -> Result<(), ContractError> {
+    if snapshot_id == 0 || snapshot_id > current(env) {
+        return Err(ContractError::InvalidSnapshot);
+    }
+    Ok(())
+}
+
+// Binary search for the first checkpoint taken at or after `snapshot_id`
+fn search<V>(count: u32, snapshot_id: u32, checkpoint: impl Fn(u32) -> (u32, V)) -> Option<V> {
+    let (mut low, mut high) = (0, count);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if checkpoint(mid).0 < snapshot_id {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    (low < count).then(|| checkpoint(low).1)
+}
@@ -1,4 +1,5 @@
-use soroban_sdk::{Address, contracttype};
+use crate::types::Role;
+use soroban_sdk::{Address, BytesN, Env, IntoVal, TryFromVal, Val, contracttype};
 
 #[derive(Clone)]
 #[contracttype]
@@ -14,10 +15,121 @@ pub enum DataKey {
     CollectionConfig,
     TotalSupply,
     TokenMetadata(u32),
-    Balance(Address, u32),
+    Balance(Address),
     Owner(u32),
     RoyaltyInfo,
-    Minter(Address),
+    TokenRoyalty(u32),
     Whitelist(Address),
     IsPaused,
+    Approved(u32),
+    OperatorApproval(Address, Address),
+    PublicMintConfig,
+    MintCount(Address),
+    MintPhases,
+    PhaseMintCount(u32, Address),
+    VoucherSigner(BytesN<32>),
+    VoucherNonce(BytesN<32>, u64),
+    Revealed,
+    MetadataFrozen,
+    CollectionOwner,
+    PendingOwner,
+    Role(Role, Address),
+    OperatorPolicy,
+    AllowedOperator(Address),
+    Locker(Address),
+    LockedBy(u32),
+    User(u32),
+    TokenByIndex(u32),
+    TokenIndex(u32),
+    OwnedToken(Address, u32),
+    CurrentSnapshot,
+    OwnerCheckpointCount(u32),
+    OwnerCheckpoint(u32, u32),
+    BalanceCheckpointCount(Address),
+    BalanceCheckpoint(Address, u32),
+    MinterLimits(Address),
+    MinterMintCount(Address),
+    NextTokenId,
+    ReservedIds,
+}
+
+/// Token metadata bookkeeping. Kept apart from `DataKey`, which is at the
+/// 50 variant limit of `#[contracttype]` enums.
+#[derive(Clone)]
+#[contracttype]
+pub enum MetadataKey {
+    MetadataVersion(u32),
+    UriHistoryCount(u32),
+    UriHistory(u32, u32),
+    OnchainMetadata,
+    SvgTemplate,
+}
+
+/// Chain of custody, kept apart from `DataKey` like `MetadataKey`.
+#[derive(Clone)]
+#[contracttype]
+pub enum ProvenanceKey {
+    ProvenanceCount(u32),
+    Provenance(u32, u32),
+    PendingSale(u32),
+    TrustedMarketplace(Address),
+}
+
+// Ledger TTLs, assuming ~5s ledgers
+pub const DAY_IN_LEDGERS: u32 = 17_280;
+pub const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+pub const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+pub const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+// Collection-wide settings stay in instance storage. Everything keyed by a
+// token id or an address lives in its own persistent entry so the instance
+// does not grow with the collection.
+
+pub fn extend_instance(env: &Env) {
+    env.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+pub fn extend_persistent(env: &Env, key: &impl IntoVal<Env, Val>) {
+    if env.storage().persistent().has(key) {
+        env.storage().persistent().extend_ttl(
+            key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+}
+
+pub fn get_persistent<V: TryFromVal<Env, Val>>(
+    env: &Env,
+    key: &impl IntoVal<Env, Val>,
+) -> Option<V> {
+    let value = env.storage().persistent().get(key);
+    if value.is_some() {
+        env.storage().persistent().extend_ttl(
+            key,
+            PERSISTENT_LIFETIME_THRESHOLD,
+            PERSISTENT_BUMP_AMOUNT,
+        );
+    }
+    value
+}
+
+pub fn set_persistent<V: IntoVal<Env, Val>>(env: &Env, key: &impl IntoVal<Env, Val>, value: &V) {
+    env.storage().persistent().set(key, value);
+    env.storage().persistent().extend_ttl(
+        key,
+        PERSISTENT_LIFETIME_THRESHOLD,
+        PERSISTENT_BUMP_AMOUNT,
+    );
+}
+
+pub fn has_persistent(env: &Env, key: &impl IntoVal<Env, Val>) -> bool {
+    env.storage().persistent().has(key)
+}
+
+pub fn remove_persistent(env: &Env, key: &impl IntoVal<Env, Val>) {
+    env.storage().persistent().remove(key);
 }
@@ -1,9 +1,26 @@
 #![cfg(test)]
+extern crate std;
 
+use crate::allowlist;
 use crate::collection::{NftCollection, NftCollectionClient};
+use crate::error::ContractError;
 use crate::factory::{CollectionFactory, CollectionFactoryClient};
-use crate::types::CollectionConfig;
-use soroban_sdk::{Address, Env, String, Vec, testutils::Address as _};
+use crate::storage::{self, DataKey, MetadataKey, ProvenanceKey};
+use crate::types::{
+    CollectionConfig, MintPhase, MintVoucher, MinterLimits, OperatorPolicy, ProvenanceEntry,
+    PublicMintConfig, Role, RoyaltyInfo, RoyaltySplit, TokenHistory,
+};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use ed25519_dalek::{Signer, SigningKey};
+use soroban_sdk::{
+    Address, Bytes, BytesN, Env, IntoVal, String, Symbol, Vec, contract, contractimpl,
+    symbol_short,
+    testutils::{
+        Address as _, EnvTestConfig, Events, Ledger,
+        storage::{Instance as _, Persistent as _},
+    },
+    token, vec,
+};
 
 #[test]
 fn test_factory_logic() {
@@ -14,7 +31,7 @@ fn test_factory_logic() {
     let _creator = Address::generate(&env);
 
     // Register Factory
-    let factory_id = env.register_contract(None, CollectionFactory);
+    let factory_id = env.register(CollectionFactory, ());
     let factory_client = CollectionFactoryClient::new(&env, &factory_id);
 
     // Initialize Factory
@@ -32,19 +49,15 @@ fn test_collection_logic() {
     let user1 = Address::generate(&env);
     let user2 = Address::generate(&env);
 
-    let collection_id = env.register_contract(None, NftCollection);
+    let collection_id = env.register(NftCollection, ());
     let collection_client = NftCollectionClient::new(&env, &collection_id);
 
     // Collection Config
     let config = CollectionConfig {
-        name: String::from_str(&env, "Test NFT"),
-        symbol: String::from_str(&env, "TNFT"),
-        description: String::from_str(&env, "Test Description"),
-        base_uri: String::from_str(&env, "https://test.com/"),
         max_supply: Some(100),
         is_public_mint: true,
         royalty_percentage: 500, // 5%
-        royalty_recipient: admin.clone(),
+        ..collection_config(&env, &admin)
     };
 
     collection_client.init(&admin, &config);
@@ -54,18 +67,21 @@ fn test_collection_logic() {
     let uri = String::from_str(&env, "ipfs://hash");
     let attributes = Vec::new(&env);
 
-    collection_client.mint(&user1, &token_id, &uri, &attributes);
+    collection_client.mint(&admin, &user1, &token_id, &uri, &attributes, &None);
 
-    assert_eq!(collection_client.owner_of(&token_id), Some(user1.clone()));
+    assert_eq!(collection_client.owner_of(&token_id), user1);
     assert_eq!(collection_client.balance_of(&user1, &token_id), 1);
+    assert_eq!(collection_client.balance(&user1), 1);
     assert_eq!(collection_client.total_supply(), 1);
 
     // Transfer NFT
     collection_client.transfer(&user1, &user2, &token_id);
 
-    assert_eq!(collection_client.owner_of(&token_id), Some(user2.clone()));
+    assert_eq!(collection_client.owner_of(&token_id), user2);
     assert_eq!(collection_client.balance_of(&user1, &token_id), 0);
     assert_eq!(collection_client.balance_of(&user2, &token_id), 1);
+    assert_eq!(collection_client.balance(&user1), 0);
+    assert_eq!(collection_client.balance(&user2), 1);
 
     // Royalty Info
     let royalty = collection_client.get_royalty_info();
@@ -79,26 +95,2298 @@ fn test_unauthorized_mint() {
     env.mock_all_auths();
 
     let admin = Address::generate(&env);
-    let _user = Address::generate(&env);
+    let user = Address::generate(&env);
 
-    let collection_id = env.register_contract(None, NftCollection);
+    let collection_id = env.register(NftCollection, ());
     let collection_client = NftCollectionClient::new(&env, &collection_id);
 
-    let config = CollectionConfig {
-        name: String::from_str(&env, "Test"),
-        symbol: String::from_str(&env, "T"),
-        description: String::from_str(&env, "D"),
-        base_uri: String::from_str(&env, "U"),
+    let config = collection_config(&env, &admin);
+
+    collection_client.init(&admin, &config);
+
+    // Try to mint from non-minter address
+    let uri = String::from_str(&env, "uri");
+    assert_eq!(
+        collection_client.try_mint(&user, &user, &1, &uri, &Vec::new(&env), &None),
+        Err(Ok(ContractError::NotMinter))
+    );
+
+    // The minter authorizes its own mint and is recorded as the creator
+    collection_client.mint(&admin, &user, &1, &uri, &Vec::new(&env), &None);
+    assert_eq!(env.auths()[0].0, admin);
+    assert_eq!(
+        collection_client.get_token_metadata(&1).unwrap().creator,
+        admin
+    );
+}
+
+fn collection_config(env: &Env, admin: &Address) -> CollectionConfig {
+    CollectionConfig {
+        name: String::from_str(env, "Test NFT"),
+        symbol: String::from_str(env, "TNFT"),
+        description: String::from_str(env, "Test Description"),
+        base_uri: String::from_str(env, "https://test.com/"),
         max_supply: None,
         is_public_mint: false,
         royalty_percentage: 0,
         royalty_recipient: admin.clone(),
+        placeholder_uri: None,
+        provenance_hash: None,
+        soulbound: false,
+        revocable: false,
+    }
+}
+
+fn setup_collection(env: &Env) -> (NftCollectionClient<'_>, Address) {
+    let admin = Address::generate(env);
+    let collection_id = env.register(NftCollection, ());
+    let collection_client = NftCollectionClient::new(env, &collection_id);
+
+    collection_client.init(&admin, &collection_config(env, &admin));
+
+    (collection_client, admin)
+}
+
+#[test]
+fn test_approve_and_transfer_from() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    let owner = Address::generate(&env);
+    let marketplace = Address::generate(&env);
+    let buyer = Address::generate(&env);
+
+    collection_client.mint(
+        &admin,
+        &owner,
+        &1,
+        &String::from_str(&env, "ipfs://1"),
+        &Vec::new(&env),
+        &None,
+    );
+
+    // Spender without approval is rejected
+    assert_eq!(
+        collection_client.try_transfer_from(&marketplace, &owner, &buyer, &1),
+        Err(Ok(ContractError::NotApproved))
+    );
+
+    let live_until_ledger = env.ledger().sequence() + 100;
+    collection_client.approve(&owner, &marketplace, &1, &live_until_ledger);
+    assert_eq!(
+        collection_client.get_approved(&1),
+        Some(marketplace.clone())
+    );
+
+    collection_client.transfer_from(&marketplace, &owner, &buyer, &1);
+    assert_eq!(collection_client.owner_of(&1), buyer);
+
+    // Approval does not survive the change of ownership
+    assert_eq!(collection_client.get_approved(&1), None);
+}
+
+#[test]
+fn test_operator_approval() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    let owner = Address::generate(&env);
+    let operator = Address::generate(&env);
+    let delegate = Address::generate(&env);
+    let buyer = Address::generate(&env);
+
+    collection_client.mint(
+        &admin,
+        &owner,
+        &1,
+        &String::from_str(&env, "ipfs://1"),
+        &Vec::new(&env),
+        &None,
+    );
+    collection_client.mint(
+        &admin,
+        &owner,
+        &2,
+        &String::from_str(&env, "ipfs://2"),
+        &Vec::new(&env),
+        &None,
+    );
+
+    collection_client.set_approval_for_all(&owner, &operator, &true);
+    assert!(collection_client.is_approved_for_all(&owner, &operator));
+
+    // An operator can move any of the owner's tokens and manage per-token approvals
+    collection_client.transfer_from(&operator, &owner, &buyer, &1);
+    collection_client.approve(&operator, &delegate, &2, &(env.ledger().sequence() + 100));
+    assert_eq!(collection_client.get_approved(&2), Some(delegate));
+
+    collection_client.set_approval_for_all(&owner, &operator, &false);
+    assert!(!collection_client.is_approved_for_all(&owner, &operator));
+    assert_eq!(
+        collection_client.try_transfer_from(&operator, &owner, &buyer, &2),
+        Err(Ok(ContractError::NotApproved))
+    );
+}
+
+#[test]
+fn test_approve_requires_owner_or_operator() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    let owner = Address::generate(&env);
+    let stranger = Address::generate(&env);
+
+    collection_client.mint(
+        &admin,
+        &owner,
+        &1,
+        &String::from_str(&env, "ipfs://1"),
+        &Vec::new(&env),
+        &None,
+    );
+
+    assert_eq!(
+        collection_client.try_approve(&stranger, &stranger, &1, &(env.ledger().sequence() + 100)),
+        Err(Ok(ContractError::NotAuthorized))
+    );
+
+    // Approvals are also cleared by a plain transfer
+    collection_client.approve(&owner, &stranger, &1, &(env.ledger().sequence() + 100));
+    collection_client.transfer(&owner, &stranger, &1);
+    assert_eq!(collection_client.get_approved(&1), None);
+}
+
+#[test]
+fn test_standard_interface() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    let owner = Address::generate(&env);
+    let recipient = Address::generate(&env);
+
+    assert_eq!(collection_client.name(), String::from_str(&env, "Test NFT"));
+    assert_eq!(collection_client.symbol(), String::from_str(&env, "TNFT"));
+
+    for token_id in 1..=3 {
+        collection_client.mint(
+            &admin,
+            &owner,
+            &token_id,
+            &String::from_str(&env, "ipfs://hash"),
+            &Vec::new(&env),
+            &None,
+        );
+    }
+    assert_eq!(collection_client.balance(&owner), 3);
+    assert_eq!(
+        collection_client.token_uri(&2),
+        String::from_str(&env, "ipfs://hash")
+    );
+
+    collection_client.transfer(&owner, &recipient, &2);
+
+    // Transfer events carry both parties as topics and the token id as data
+    let events = env.events().all();
+    assert_eq!(
+        events.slice(events.len() - 1..),
+        vec![
+            &env,
+            (
+                collection_client.address.clone(),
+                (
+                    Symbol::new(&env, "transfer"),
+                    owner.clone(),
+                    recipient.clone()
+                )
+                    .into_val(&env),
+                2u32.into_val(&env),
+            )
+        ]
+    );
+
+    assert_eq!(collection_client.balance(&owner), 2);
+    assert_eq!(collection_client.balance(&recipient), 1);
+
+    collection_client.burn(&recipient, &2);
+    assert_eq!(collection_client.balance(&recipient), 0);
+
+    // Unknown tokens are reported as errors rather than empty values
+    assert_eq!(
+        collection_client.try_owner_of(&2),
+        Err(Ok(ContractError::NotFound))
+    );
+    assert_eq!(
+        collection_client.try_token_uri(&2),
+        Err(Ok(ContractError::NotFound))
+    );
+}
+
+#[test]
+fn test_approvals_expire() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    let owner = Address::generate(&env);
+    let spender = Address::generate(&env);
+    let operator = Address::generate(&env);
+
+    collection_client.mint(
+        &admin,
+        &owner,
+        &1,
+        &String::from_str(&env, "ipfs://1"),
+        &Vec::new(&env),
+        &None,
+    );
+
+    env.ledger().set_sequence_number(100);
+    let sequence = env.ledger().sequence();
+    assert_eq!(
+        collection_client.try_approve(&owner, &spender, &1, &(sequence - 1)),
+        Err(Ok(ContractError::InvalidLiveUntilLedger))
+    );
+
+    collection_client.approve(&owner, &spender, &1, &(sequence + 10));
+    collection_client.approve_for_all(&owner, &operator, &(sequence + 10));
+    assert_eq!(collection_client.get_approved(&1), Some(spender.clone()));
+    assert!(collection_client.is_approved_for_all(&owner, &operator));
+
+    env.ledger().set_sequence_number(sequence + 11);
+    assert_eq!(collection_client.get_approved(&1), None);
+    assert!(!collection_client.is_approved_for_all(&owner, &operator));
+    assert_eq!(
+        collection_client.try_transfer_from(&spender, &owner, &spender, &1),
+        Err(Ok(ContractError::NotApproved))
+    );
+
+    // A zero ledger revokes an approval before it expires
+    let sequence = env.ledger().sequence();
+    collection_client.approve(&owner, &spender, &1, &(sequence + 10));
+    collection_client.approve(&owner, &spender, &1, &0);
+    assert_eq!(collection_client.get_approved(&1), None);
+}
+
+#[test]
+fn test_token_state_ttl() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    collection_client.mint(
+        &admin,
+        &owner,
+        &1,
+        &String::from_str(&env, "ipfs://1"),
+        &Vec::new(&env),
+        &None,
+    );
+    // Give the token a URI, ownership and provenance history
+    collection_client.update_token_metadata(&admin, &1, &String::from_str(&env, "ipfs://2"));
+    collection_client.update_token_metadata(&admin, &1, &String::from_str(&env, "ipfs://3"));
+    collection_client.snapshot();
+    collection_client.transfer(&owner, &buyer, &1);
+    collection_client.transfer(&buyer, &owner, &1);
+
+    let ttl = |key: &DataKey| {
+        env.as_contract(&collection_client.address, || {
+            env.storage().persistent().get_ttl(key)
+        })
+    };
+    let metadata_ttl = |key: &MetadataKey| {
+        env.as_contract(&collection_client.address, || {
+            env.storage().persistent().get_ttl(key)
+        })
+    };
+    let provenance_ttl = |key: &ProvenanceKey| {
+        env.as_contract(&collection_client.address, || {
+            env.storage().persistent().get_ttl(key)
+        })
     };
+    assert_eq!(ttl(&DataKey::Owner(1)), storage::PERSISTENT_BUMP_AMOUNT);
+    assert_eq!(
+        ttl(&DataKey::TokenMetadata(1)),
+        storage::PERSISTENT_BUMP_AMOUNT
+    );
 
-    collection_client.init(&admin, &config);
+    // Past the threshold a keeper can top the token entries back up
+    let elapsed = storage::PERSISTENT_BUMP_AMOUNT - storage::PERSISTENT_LIFETIME_THRESHOLD + 1;
+    env.ledger()
+        .set_sequence_number(env.ledger().sequence() + elapsed);
+    let decayed = storage::PERSISTENT_BUMP_AMOUNT - elapsed;
+    assert_eq!(ttl(&DataKey::Owner(1)), decayed);
 
-    // Try to mint from non-minter address
-    // collection_client.mint(&user, &1, &String::from_str(&env, "uri"), &Vec::new(&env));
-    // Wait, the mint function checks if the env.storage().instance().get(&DataKey::FactoryAdmin) is the minter.
-    // Actually, it checks Self::is_minter(&env, &admin).
+    collection_client.extend_ttl(&vec![&env, 1]);
+    for key in [
+        DataKey::Owner(1),
+        DataKey::TokenMetadata(1),
+        DataKey::Balance(owner.clone()),
+        DataKey::OwnerCheckpointCount(1),
+    ] {
+        assert_eq!(ttl(&key), storage::PERSISTENT_BUMP_AMOUNT);
+    }
+    assert_eq!(
+        metadata_ttl(&MetadataKey::MetadataVersion(1)),
+        storage::PERSISTENT_BUMP_AMOUNT
+    );
+    assert_eq!(
+        metadata_ttl(&MetadataKey::UriHistoryCount(1)),
+        storage::PERSISTENT_BUMP_AMOUNT
+    );
+    assert_eq!(
+        provenance_ttl(&ProvenanceKey::ProvenanceCount(1)),
+        storage::PERSISTENT_BUMP_AMOUNT
+    );
+    assert_eq!(
+        env.as_contract(&collection_client.address, || {
+            env.storage().instance().get_ttl()
+        }),
+        storage::INSTANCE_BUMP_AMOUNT
+    );
+
+    // History entries are left to extend_history_ttl, one page at a time
+    assert_eq!(metadata_ttl(&MetadataKey::UriHistory(1, 0)), decayed);
+    assert_eq!(ttl(&DataKey::OwnerCheckpoint(1, 0)), decayed);
+    collection_client.extend_history_ttl(&1, &TokenHistory::Provenance, &1, &1);
+    assert_eq!(provenance_ttl(&ProvenanceKey::Provenance(1, 0)), decayed);
+    assert_eq!(
+        provenance_ttl(&ProvenanceKey::Provenance(1, 1)),
+        storage::PERSISTENT_BUMP_AMOUNT
+    );
+    assert_eq!(provenance_ttl(&ProvenanceKey::Provenance(1, 2)), decayed);
+
+    // Pages are cut off at the end of the history
+    collection_client.extend_history_ttl(&1, &TokenHistory::Provenance, &2, &10);
+    assert_eq!(
+        provenance_ttl(&ProvenanceKey::Provenance(1, 2)),
+        storage::PERSISTENT_BUMP_AMOUNT
+    );
+    collection_client.extend_history_ttl(&1, &TokenHistory::Uri, &0, &10);
+    for index in 0..2 {
+        assert_eq!(
+            metadata_ttl(&MetadataKey::UriHistory(1, index)),
+            storage::PERSISTENT_BUMP_AMOUNT
+        );
+    }
+    collection_client.extend_history_ttl(&1, &TokenHistory::Owner, &0, &10);
+    assert_eq!(
+        ttl(&DataKey::OwnerCheckpoint(1, 0)),
+        storage::PERSISTENT_BUMP_AMOUNT
+    );
+}
+
+#[test]
+fn test_resource_usage_stays_flat() {
+    // Skip the ledger snapshot file, it would hold every minted token
+    let env = Env::new_with_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    });
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    let owner = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    let uri = String::from_str(&env, "ipfs://hash");
+
+    collection_client.mint(&admin, &owner, &1, &uri, &Vec::new(&env), &None);
+    collection_client.mint(&admin, &owner, &2, &uri, &Vec::new(&env), &None);
+    let first_mint = env.cost_estimate().resources();
+    collection_client.transfer(&owner, &recipient, &2);
+    let first_transfer = env.cost_estimate().resources();
+
+    for token_id in 3..=300 {
+        collection_client.mint(&admin, &owner, &token_id, &uri, &Vec::new(&env), &None);
+    }
+    let last_mint = env.cost_estimate().resources();
+    collection_client.transfer(&owner, &recipient, &300);
+    let last_transfer = env.cost_estimate().resources();
+
+    // A large collection touches the same entries and bytes as a fresh one
+    for (first, last) in [(first_mint, last_mint), (first_transfer, last_transfer)] {
+        assert_eq!(first.memory_read_entries, last.memory_read_entries);
+        assert_eq!(first.write_entries, last.write_entries);
+        assert_eq!(first.write_bytes, last.write_bytes);
+    }
+}
+
+#[test]
+fn test_public_mint() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let payout = Address::generate(&env);
+    let buyer = Address::generate(&env);
+
+    let collection_id = env.register(NftCollection, ());
+    let collection_client = NftCollectionClient::new(&env, &collection_id);
+    collection_client.init(
+        &admin,
+        &CollectionConfig {
+            max_supply: Some(3),
+            is_public_mint: true,
+            ..collection_config(&env, &admin)
+        },
+    );
+
+    // Nothing can be minted publicly before a price is configured
+    assert_eq!(
+        collection_client.try_public_mint(&buyer),
+        Err(Ok(ContractError::PublicMintDisabled))
+    );
+
+    let sac = env.register_stellar_asset_contract_v2(admin.clone());
+    token::StellarAssetClient::new(&env, &sac.address()).mint(&buyer, &1_000);
+    let payment = token::Client::new(&env, &sac.address());
+
+    collection_client.set_public_mint(&PublicMintConfig {
+        payment_token: sac.address(),
+        price: 300,
+        wallet_limit: 2,
+        payout_address: payout.clone(),
+    });
+
+    assert_eq!(collection_client.public_mint(&buyer), 1);
+    assert_eq!(collection_client.public_mint(&buyer), 2);
+    assert_eq!(collection_client.balance(&buyer), 2);
+    assert_eq!(collection_client.public_mint_count(&buyer), 2);
+    assert_eq!(payment.balance(&payout), 600);
+    assert_eq!(payment.balance(&buyer), 400);
+
+    // Buyers pay for their tokens but the collection's creator made them
+    let metadata = collection_client.get_token_metadata(&1).unwrap();
+    assert_eq!(metadata.creator, admin);
+    let mint = collection_client.provenance(&1, &0, &1).get_unchecked(0);
+    assert_eq!(mint.operator, admin);
+    assert_eq!(mint.to, Some(buyer.clone()));
+
+    assert_eq!(
+        collection_client.try_public_mint(&buyer),
+        Err(Ok(ContractError::MintLimitExceeded))
+    );
+
+    // A wallet that cannot pay gets nothing
+    let broke = Address::generate(&env);
+    assert_eq!(
+        collection_client.try_public_mint(&broke),
+        Err(Ok(ContractError::InsufficientBalance))
+    );
+    assert_eq!(collection_client.total_supply(), 2);
+}
+
+#[test]
+fn test_public_mint_requires_flag() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    let buyer = Address::generate(&env);
+
+    collection_client.set_public_mint(&PublicMintConfig {
+        payment_token: Address::generate(&env),
+        price: 0,
+        wallet_limit: 0,
+        payout_address: admin,
+    });
+
+    assert_eq!(
+        collection_client.try_public_mint(&buyer),
+        Err(Ok(ContractError::PublicMintDisabled))
+    );
+}
+
+#[test]
+fn test_mint_phases() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let payout = Address::generate(&env);
+    let collection_id = env.register(NftCollection, ());
+    let collection_client = NftCollectionClient::new(&env, &collection_id);
+    collection_client.init(
+        &admin,
+        &CollectionConfig {
+            is_public_mint: true,
+            ..collection_config(&env, &admin)
+        },
+    );
+
+    let sac = env.register_stellar_asset_contract_v2(admin.clone());
+    let sac_admin = token::StellarAssetClient::new(&env, &sac.address());
+    let payment = token::Client::new(&env, &sac.address());
+
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let carol = Address::generate(&env);
+    let dave = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    for wallet in [&alice, &bob, &carol, &dave, &stranger] {
+        sac_admin.mint(wallet, &1_000);
+    }
+
+    // Alice, Bob and Carol are in the Merkle tree; Dave has an allowlist entry
+    let (alice_leaf, bob_leaf, carol_leaf) = (
+        allowlist::leaf(&env, &alice),
+        allowlist::leaf(&env, &bob),
+        allowlist::leaf(&env, &carol),
+    );
+    let root = allowlist::hash_pair(
+        &env,
+        &allowlist::hash_pair(&env, &alice_leaf, &bob_leaf),
+        &carol_leaf,
+    );
+    let bob_proof = vec![&env, alice_leaf, carol_leaf];
+    collection_client.set_allowlist(&vec![&env, dave.clone()], &true);
+
+    collection_client.set_public_mint(&PublicMintConfig {
+        payment_token: sac.address(),
+        price: 500,
+        wallet_limit: 0,
+        payout_address: payout.clone(),
+    });
+    collection_client.set_mint_phases(&vec![
+        &env,
+        MintPhase {
+            start_time: 1_000,
+            end_time: 2_000,
+            price: 100,
+            allocation: 1,
+            allowlist: true,
+            merkle_root: Some(root),
+        },
+        MintPhase {
+            start_time: 2_000,
+            end_time: 3_000,
+            price: 300,
+            allocation: 2,
+            allowlist: false,
+            merkle_root: None,
+        },
+    ]);
+
+    env.ledger().set_timestamp(500);
+    assert_eq!(
+        collection_client.try_allowlist_mint(&bob, &bob_proof),
+        Err(Ok(ContractError::MintPhaseNotActive))
+    );
+
+    // Presale
+    env.ledger().set_timestamp(1_500);
+    assert_eq!(
+        collection_client.try_public_mint(&stranger),
+        Err(Ok(ContractError::MintPhaseNotActive))
+    );
+    collection_client.allowlist_mint(&bob, &bob_proof);
+    assert_eq!(payment.balance(&payout), 100);
+    assert_eq!(collection_client.phase_mint_count(&0, &bob), 1);
+    assert_eq!(
+        collection_client.try_allowlist_mint(&bob, &bob_proof),
+        Err(Ok(ContractError::MintLimitExceeded))
+    );
+    assert_eq!(
+        collection_client.try_allowlist_mint(&stranger, &bob_proof),
+        Err(Ok(ContractError::NotAllowlisted))
+    );
+    collection_client.allowlist_mint(&dave, &Vec::new(&env));
+    assert!(collection_client.is_allowlisted(&dave));
+
+    // Public sale at the public phase price
+    env.ledger().set_timestamp(2_000);
+    assert_eq!(collection_client.public_mint(&stranger), 3);
+    assert_eq!(collection_client.public_mint(&bob), 4);
+    assert_eq!(payment.balance(&payout), 100 + 100 + 300 + 300);
+    assert_eq!(collection_client.owner_of(&3), stranger);
+
+    env.ledger().set_timestamp(3_000);
+    assert_eq!(
+        collection_client.try_public_mint(&stranger),
+        Err(Ok(ContractError::MintPhaseNotActive))
+    );
+}
+
+#[test]
+fn test_free_allowlist_phase_needs_no_payment_config() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let member = Address::generate(&env);
+    let collection_id = env.register(NftCollection, ());
+    let collection_client = NftCollectionClient::new(&env, &collection_id);
+    collection_client.init(
+        &admin,
+        &CollectionConfig {
+            is_public_mint: true,
+            ..collection_config(&env, &admin)
+        },
+    );
+    collection_client.set_allowlist(&vec![&env, member.clone()], &true);
+    collection_client.set_mint_phases(&vec![
+        &env,
+        MintPhase {
+            start_time: 1_000,
+            end_time: 2_000,
+            price: 0,
+            allocation: 1,
+            allowlist: true,
+            merkle_root: None,
+        },
+        MintPhase {
+            start_time: 2_000,
+            end_time: 3_000,
+            price: 300,
+            allocation: 0,
+            allowlist: false,
+            merkle_root: None,
+        },
+    ]);
+
+    env.ledger().set_timestamp(1_500);
+    collection_client.allowlist_mint(&member, &Vec::new(&env));
+    assert_eq!(collection_client.owner_of(&1), member);
+
+    // A paid phase still needs to know where the payment goes
+    env.ledger().set_timestamp(2_500);
+    assert_eq!(
+        collection_client.try_public_mint(&member),
+        Err(Ok(ContractError::PublicMintDisabled))
+    );
+}
+
+#[test]
+fn test_mint_phases_must_not_overlap() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, _admin) = setup_collection(&env);
+    let phase = |start_time, end_time| MintPhase {
+        start_time,
+        end_time,
+        price: 0,
+        allocation: 0,
+        allowlist: false,
+        merkle_root: None,
+    };
+
+    assert_eq!(
+        collection_client.try_set_mint_phases(&vec![&env, phase(100, 200), phase(150, 300)]),
+        Err(Ok(ContractError::InvalidMintPhase))
+    );
+    assert_eq!(
+        collection_client.try_set_mint_phases(&vec![&env, phase(200, 200)]),
+        Err(Ok(ContractError::InvalidMintPhase))
+    );
+    collection_client.set_mint_phases(&vec![&env, phase(100, 200), phase(200, 300)]);
+    assert_eq!(collection_client.get_mint_phases().len(), 2);
+}
+
+#[test]
+fn test_batch_mint_and_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let collection_id = env.register(NftCollection, ());
+    let collection_client = NftCollectionClient::new(&env, &collection_id);
+    collection_client.init(
+        &admin,
+        &CollectionConfig {
+            max_supply: Some(4),
+            ..collection_config(&env, &admin)
+        },
+    );
+
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let uris = |count| {
+        let mut uris = Vec::new(&env);
+        for _ in 0..count {
+            uris.push_back(String::from_str(&env, "ipfs://hash"));
+        }
+        uris
+    };
+    let attributes = |count| {
+        let mut attributes = Vec::new(&env);
+        for _ in 0..count {
+            attributes.push_back(Vec::<(String, String)>::new(&env));
+        }
+        attributes
+    };
+
+    // Mismatched inputs, duplicate ids and an exceeded cap all mint nothing
+    assert_eq!(
+        collection_client.try_batch_mint(
+            &admin,
+            &vec![&env, alice.clone()],
+            &vec![&env, 1, 2],
+            &uris(2),
+            &attributes(2)
+        ),
+        Err(Ok(ContractError::InvalidAmount))
+    );
+    assert_eq!(
+        collection_client.try_batch_mint(
+            &admin,
+            &vec![&env, alice.clone(), bob.clone()],
+            &vec![&env, 1, 1],
+            &uris(2),
+            &attributes(2)
+        ),
+        Err(Ok(ContractError::TokenAlreadyExists))
+    );
+    assert_eq!(
+        collection_client.try_batch_mint(
+            &admin,
+            &vec![
+                &env,
+                alice.clone(),
+                alice.clone(),
+                alice.clone(),
+                bob.clone(),
+                bob.clone()
+            ],
+            &vec![&env, 1, 2, 3, 4, 5],
+            &uris(5),
+            &attributes(5)
+        ),
+        Err(Ok(ContractError::SupplyLimitExceeded))
+    );
+    assert_eq!(collection_client.total_supply(), 0);
+
+    let recipients = vec![&env, alice.clone(), alice.clone(), bob.clone()];
+    let token_ids = vec![&env, 1, 2, 3];
+    collection_client.batch_mint(&admin, &recipients, &token_ids, &uris(3), &attributes(3));
+
+    // One event covers the whole batch
+    let events = env.events().all();
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events.get_unchecked(0).1,
+        (Symbol::new(&env, "batch_mint"),).into_val(&env)
+    );
+
+    assert_eq!(collection_client.total_supply(), 3);
+    assert_eq!(collection_client.balance(&alice), 2);
+    assert_eq!(collection_client.balance(&bob), 1);
+    assert_eq!(collection_client.owner_of(&3), bob);
+
+    // Alice does not own token 3, so neither of her tokens moves
+    assert_eq!(
+        collection_client.try_batch_transfer(&alice, &bob, &vec![&env, 1, 3]),
+        Err(Ok(ContractError::NotAuthorized))
+    );
+    assert_eq!(collection_client.owner_of(&1), alice);
+
+    collection_client.batch_transfer(&alice, &bob, &vec![&env, 1, 2]);
+    let events = env.events().all();
+    assert_eq!(
+        events,
+        vec![
+            &env,
+            (
+                collection_id.clone(),
+                (
+                    Symbol::new(&env, "batch_transfer"),
+                    alice.clone(),
+                    bob.clone()
+                )
+                    .into_val(&env),
+                vec![&env, 1u32, 2u32].into_val(&env),
+            )
+        ]
+    );
+    assert_eq!(collection_client.balance(&alice), 0);
+    assert_eq!(collection_client.balance(&bob), 3);
+    assert_eq!(collection_client.owner_of(&2), bob);
+    assert_eq!(collection_client.tokens_of_owner(&bob, &0, &3).len(), 3);
+    // Batched moves are logged like single transfers
+    let moved = collection_client.provenance(&2, &1, &1).get_unchecked(0);
+    assert_eq!(moved.from, Some(alice.clone()));
+    assert_eq!(moved.to, Some(bob.clone()));
+}
+
+fn sign_voucher(
+    collection_client: &NftCollectionClient,
+    key: &SigningKey,
+    voucher: &MintVoucher,
+) -> BytesN<64> {
+    let message: std::vec::Vec<u8> = collection_client.voucher_message(voucher).iter().collect();
+    BytesN::from_array(&collection_client.env, &key.sign(&message).to_bytes())
+}
+
+#[test]
+fn test_lazy_mint_voucher() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let (collection_client, admin) = setup_collection(&env);
+    let minter = Address::generate(&env);
+    let payout = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    collection_client.grant_role(&Role::Minter, &minter);
+
+    let sac = env.register_stellar_asset_contract_v2(admin.clone());
+    token::StellarAssetClient::new(&env, &sac.address()).mint(&buyer, &1_000);
+    let payment = token::Client::new(&env, &sac.address());
+
+    let key = SigningKey::from_bytes(&[7; 32]);
+    let signer = BytesN::from_array(&env, &key.verifying_key().to_bytes());
+    let voucher = |token_id, nonce| MintVoucher {
+        token_id,
+        uri: String::from_str(&env, "ipfs://lazy"),
+        attributes: Vec::new(&env),
+        content_hash: None,
+        price: 250,
+        payment_token: sac.address(),
+        payout: payout.clone(),
+        expiry: 2_000,
+        nonce,
+    };
+
+    // Vouchers from keys the creator has not authorized are rejected
+    let first = voucher(1, 1);
+    let signature = sign_voucher(&collection_client, &key, &first);
+    assert_eq!(
+        collection_client.try_redeem_voucher(&buyer, &first, &signer, &signature),
+        Err(Ok(ContractError::NotMinter))
+    );
+
+    collection_client.set_voucher_signer(&signer, &Some(minter.clone()));
+    collection_client.redeem_voucher(&buyer, &first, &signer, &signature);
+    assert_eq!(collection_client.owner_of(&1), buyer);
+    assert_eq!(
+        collection_client.get_token_metadata(&1).unwrap().creator,
+        minter
+    );
+    assert_eq!(collection_client.minted_by(&minter), 1);
+    assert_eq!(
+        collection_client.token_uri(&1),
+        String::from_str(&env, "ipfs://lazy")
+    );
+    assert_eq!(payment.balance(&payout), 250);
+    assert!(collection_client.is_voucher_used(&signer, &1));
+
+    // Each nonce redeems once
+    let replay = voucher(2, 1);
+    let replay_signature = sign_voucher(&collection_client, &key, &replay);
+    assert_eq!(
+        collection_client.try_redeem_voucher(&buyer, &replay, &signer, &replay_signature),
+        Err(Ok(ContractError::VoucherAlreadyUsed))
+    );
+
+    // A voucher altered after signing fails verification
+    let mut tampered = voucher(2, 2);
+    let tampered_signature = sign_voucher(&collection_client, &key, &tampered);
+    tampered.payout = buyer.clone();
+    assert!(
+        collection_client
+            .try_redeem_voucher(&buyer, &tampered, &signer, &tampered_signature)
+            .is_err()
+    );
+
+    // Vouchers draw on the signer's minter quota and role
+    let over_quota = voucher(5, 5);
+    let over_quota_signature = sign_voucher(&collection_client, &key, &over_quota);
+    collection_client.set_minter_limits(
+        &minter,
+        &MinterLimits {
+            quota: 1,
+            expires: 0,
+        },
+    );
+    assert_eq!(
+        collection_client.try_redeem_voucher(&buyer, &over_quota, &signer, &over_quota_signature),
+        Err(Ok(ContractError::MintLimitExceeded))
+    );
+    collection_client.set_minter_limits(&minter, &MinterLimits::default());
+    collection_client.revoke_role(&Role::Minter, &minter);
+    assert_eq!(
+        collection_client.try_redeem_voucher(&buyer, &over_quota, &signer, &over_quota_signature),
+        Err(Ok(ContractError::NotMinter))
+    );
+    collection_client.grant_role(&Role::Minter, &minter);
+
+    // Cancelled vouchers can no longer be redeemed
+    let cancelled = voucher(3, 3);
+    let cancelled_signature = sign_voucher(&collection_client, &key, &cancelled);
+    assert_eq!(
+        collection_client.try_cancel_voucher(&buyer, &signer, &3),
+        Err(Ok(ContractError::NotAuthorized))
+    );
+    collection_client.cancel_voucher(&minter, &signer, &3);
+    assert_eq!(
+        collection_client.try_redeem_voucher(&buyer, &cancelled, &signer, &cancelled_signature),
+        Err(Ok(ContractError::VoucherAlreadyUsed))
+    );
+    // The collection owner can cancel any signer's vouchers
+    collection_client.cancel_voucher(&admin, &signer, &6);
+    assert!(collection_client.is_voucher_used(&signer, &6));
+
+    env.ledger().set_timestamp(2_001);
+    let expired = voucher(4, 4);
+    let expired_signature = sign_voucher(&collection_client, &key, &expired);
+    assert_eq!(
+        collection_client.try_redeem_voucher(&buyer, &expired, &signer, &expired_signature),
+        Err(Ok(ContractError::VoucherExpired))
+    );
+    assert_eq!(collection_client.total_supply(), 1);
+}
+
+#[test]
+fn test_delayed_reveal_and_freeze() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let provenance = BytesN::from_array(&env, &[9; 32]);
+
+    let collection_id = env.register(NftCollection, ());
+    let collection_client = NftCollectionClient::new(&env, &collection_id);
+    collection_client.init(
+        &admin,
+        &CollectionConfig {
+            base_uri: String::from_str(&env, ""),
+            placeholder_uri: Some(String::from_str(&env, "ipfs://placeholder")),
+            provenance_hash: Some(provenance.clone()),
+            ..collection_config(&env, &admin)
+        },
+    );
+    assert_eq!(collection_client.provenance_hash(), Some(provenance));
+    assert!(!collection_client.is_revealed());
+
+    let empty = String::from_str(&env, "");
+    collection_client.mint(&admin, &owner, &7, &empty, &Vec::new(&env), &None);
+    collection_client.mint(
+        &admin,
+        &owner,
+        &8,
+        &String::from_str(&env, "ipfs://one-of-one"),
+        &Vec::new(&env),
+        &None,
+    );
+
+    assert_eq!(
+        collection_client.token_uri(&7),
+        String::from_str(&env, "ipfs://placeholder")
+    );
+    // Tokens with their own URI stay hidden until reveal too
+    assert_eq!(
+        collection_client.token_uri(&8),
+        String::from_str(&env, "ipfs://placeholder")
+    );
+    assert_eq!(
+        collection_client.get_token_uri(&8),
+        Some(String::from_str(&env, "ipfs://placeholder"))
+    );
+    assert_eq!(
+        collection_client.try_freeze_metadata(&admin),
+        Err(Ok(ContractError::NotRevealed))
+    );
+
+    collection_client.reveal(&admin, &String::from_str(&env, "ipfs://revealed/"));
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            (
+                collection_id.clone(),
+                (Symbol::new(&env, "reveal"),).into_val(&env),
+                String::from_str(&env, "ipfs://revealed/").into_val(&env),
+            ),
+            (
+                collection_id.clone(),
+                (Symbol::new(&env, "batch_metadata_update"),).into_val(&env),
+                (0u32, u32::MAX).into_val(&env),
+            )
+        ]
+    );
+    assert_eq!(
+        collection_client.token_uri(&7),
+        String::from_str(&env, "ipfs://revealed/7")
+    );
+    // After reveal a per-token URI wins over the collection URI
+    assert_eq!(
+        collection_client.token_uri(&8),
+        String::from_str(&env, "ipfs://one-of-one")
+    );
+    assert_eq!(
+        collection_client.try_reveal(&admin, &String::from_str(&env, "ipfs://other/")),
+        Err(Ok(ContractError::AlreadyRevealed))
+    );
+
+    collection_client.freeze_metadata(&admin);
+    assert!(collection_client.is_metadata_frozen());
+    assert_eq!(
+        collection_client.try_freeze_metadata(&admin),
+        Err(Ok(ContractError::MetadataFrozen))
+    );
+}
+
+#[test]
+fn test_token_uri_from_base_uri() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    let owner = Address::generate(&env);
+    let empty = String::from_str(&env, "");
+
+    collection_client.mint(&admin, &owner, &0, &empty, &Vec::new(&env), &None);
+    collection_client.mint(
+        &admin,
+        &owner,
+        &4_294_967_295,
+        &empty,
+        &Vec::new(&env),
+        &None,
+    );
+
+    assert!(collection_client.is_revealed());
+    assert_eq!(
+        collection_client.token_uri(&0),
+        String::from_str(&env, "https://test.com/0")
+    );
+    assert_eq!(
+        collection_client.get_token_uri(&4_294_967_295),
+        Some(String::from_str(&env, "https://test.com/4294967295"))
+    );
+}
+
+fn decode_data_uri(uri: &String, prefix: &str) -> std::string::String {
+    let mut buf = std::vec![0u8; uri.len() as usize];
+    uri.copy_into_slice(&mut buf);
+    let encoded = std::str::from_utf8(&buf)
+        .unwrap()
+        .strip_prefix(prefix)
+        .unwrap();
+    std::string::String::from_utf8(BASE64_STANDARD.decode(encoded).unwrap()).unwrap()
+}
+
+#[test]
+fn test_onchain_metadata() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    let owner = Address::generate(&env);
+    let uri = String::from_str(&env, "ipfs://token");
+    let attributes = vec![
+        &env,
+        (
+            String::from_str(&env, "color"),
+            String::from_str(&env, "red"),
+        ),
+        (
+            String::from_str(&env, "motto"),
+            String::from_str(&env, "<3 \"gm\""),
+        ),
+    ];
+    collection_client.mint(&admin, &owner, &7, &uri, &attributes, &None);
+    assert_eq!(collection_client.token_uri(&7), uri);
+
+    collection_client.set_onchain_metadata(&admin, &true, &None);
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            (
+                collection_client.address.clone(),
+                (Symbol::new(&env, "batch_metadata_update"),).into_val(&env),
+                (0u32, u32::MAX).into_val(&env),
+            )
+        ]
+    );
+    assert_eq!(
+        decode_data_uri(
+            &collection_client.token_uri(&7),
+            "data:application/json;base64,"
+        ),
+        concat!(
+            r#"{"name":"Test NFT #7","description":"Test Description","#,
+            r#""attributes":[{"trait_type":"color","value":"red"},"#,
+            r#"{"trait_type":"motto","value":"<3 \"gm\""}]}"#
+        )
+    );
+
+    // The template renders attributes into an embedded SVG image
+    let template = String::from_str(
+        &env,
+        "<svg><text fill=\"{color}\">{name} #{id}: {motto}{missing}</text></svg>",
+    );
+    collection_client.set_onchain_metadata(&admin, &true, &Some(template.clone()));
+    assert_eq!(collection_client.svg_template(), Some(template));
+    let svg = "<svg><text fill=\"red\">Test NFT #7: &lt;3 &quot;gm&quot;</text></svg>";
+    assert_eq!(
+        decode_data_uri(
+            &collection_client.token_uri(&7),
+            "data:application/json;base64,"
+        ),
+        std::format!(
+            concat!(
+                r#"{{"name":"Test NFT #7","description":"Test Description","#,
+                r#""image":"data:image/svg+xml;base64,{}","#,
+                r#""attributes":[{{"trait_type":"color","value":"red"}},"#,
+                r#"{{"trait_type":"motto","value":"<3 \"gm\""}}]}}"#
+            ),
+            BASE64_STANDARD.encode(svg)
+        )
+    );
+
+    // Frozen metadata can't switch modes
+    collection_client.freeze_metadata(&admin);
+    assert_eq!(
+        collection_client.try_set_onchain_metadata(&admin, &false, &None),
+        Err(Ok(ContractError::MetadataFrozen))
+    );
+    assert!(collection_client.is_onchain_metadata());
+}
+
+#[test]
+fn test_metadata_updates() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    let owner = Address::generate(&env);
+    let curator = Address::generate(&env);
+
+    let first_uri = String::from_str(&env, "ipfs://v1");
+    collection_client.mint(&admin, &owner, &1, &first_uri, &Vec::new(&env), &None);
+    assert_eq!(collection_client.metadata_version(&1), 0);
+
+    // Only metadata admins may edit
+    assert_eq!(
+        collection_client.try_update_token_metadata(&curator, &1, &String::from_str(&env, "x")),
+        Err(Ok(ContractError::NotAuthorized))
+    );
+    collection_client.grant_role(&Role::MetadataAdmin, &curator);
+
+    let second_uri = String::from_str(&env, "ipfs://v2");
+    assert_eq!(
+        collection_client.update_token_metadata(&curator, &1, &second_uri),
+        1
+    );
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            (
+                collection_client.address.clone(),
+                (Symbol::new(&env, "metadata_update"),).into_val(&env),
+                1u32.into_val(&env),
+            )
+        ]
+    );
+
+    let attributes = vec![
+        &env,
+        (
+            String::from_str(&env, "rarity"),
+            String::from_str(&env, "legendary"),
+        ),
+    ];
+    assert_eq!(
+        collection_client.update_attributes(&curator, &1, &attributes),
+        2
+    );
+    collection_client.update_token_metadata(&admin, &1, &String::from_str(&env, "ipfs://v3"));
+
+    let metadata = collection_client.get_token_metadata(&1).unwrap();
+    assert_eq!(metadata.uri, String::from_str(&env, "ipfs://v3"));
+    assert_eq!(metadata.attributes, attributes);
+    assert_eq!(collection_client.metadata_version(&1), 3);
+    assert_eq!(
+        collection_client.uri_history(&1, &0, &10),
+        vec![&env, first_uri, second_uri.clone()]
+    );
+    assert_eq!(
+        collection_client.uri_history(&1, &1, &10),
+        vec![&env, second_uri]
+    );
+
+    assert_eq!(
+        collection_client.try_update_attributes(&curator, &2, &attributes),
+        Err(Ok(ContractError::NotFound))
+    );
+
+    collection_client.revoke_role(&Role::MetadataAdmin, &curator);
+    assert_eq!(
+        collection_client.try_update_attributes(&curator, &1, &attributes),
+        Err(Ok(ContractError::NotAuthorized))
+    );
+}
+
+#[test]
+fn test_content_hash() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    let owner = Address::generate(&env);
+    let uri = String::from_str(&env, "ipfs://artwork");
+    let artwork: BytesN<32> = env
+        .crypto()
+        .sha256(&Bytes::from_slice(&env, b"artwork"))
+        .into();
+    let remaster: BytesN<32> = env
+        .crypto()
+        .sha256(&Bytes::from_slice(&env, b"remaster"))
+        .into();
+
+    collection_client.mint(
+        &admin,
+        &owner,
+        &1,
+        &uri,
+        &Vec::new(&env),
+        &Some(artwork.clone()),
+    );
+    collection_client.mint(&admin, &owner, &2, &uri, &Vec::new(&env), &None);
+    assert_eq!(collection_client.content_hash(&1), Some(artwork.clone()));
+    assert!(collection_client.verify_content(&1, &artwork));
+    assert!(!collection_client.verify_content(&1, &remaster));
+    assert!(!collection_client.verify_content(&2, &artwork));
+    assert!(!collection_client.verify_content(&3, &artwork));
+
+    // Re-pinning counts as a metadata update
+    assert_eq!(
+        collection_client.set_content_hash(&admin, &1, &Some(remaster.clone())),
+        1
+    );
+    assert!(collection_client.verify_content(&1, &remaster));
+    assert_eq!(
+        collection_client.try_set_content_hash(&owner, &1, &None),
+        Err(Ok(ContractError::NotAuthorized))
+    );
+
+    // Frozen metadata pins the hash for good
+    collection_client.freeze_metadata(&admin);
+    assert_eq!(
+        collection_client.try_set_content_hash(&admin, &1, &Some(artwork)),
+        Err(Ok(ContractError::MetadataFrozen))
+    );
+    assert!(collection_client.verify_content(&1, &remaster));
+}
+
+#[test]
+fn test_royalties() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    // 5% to the admin collection-wide
+    collection_client.set_royalty_info(&admin, &admin, &500);
+    let owner = Address::generate(&env);
+    let artist = Address::generate(&env);
+    let gallery = Address::generate(&env);
+
+    let uri = String::from_str(&env, "ipfs://hash");
+    collection_client.mint(&admin, &owner, &1, &uri, &Vec::new(&env), &None);
+    collection_client.mint(&admin, &owner, &2, &uri, &Vec::new(&env), &None);
+
+    assert_eq!(
+        collection_client.royalty_info(&1, &10_000),
+        (admin.clone(), 500)
+    );
+
+    let splits = vec![
+        &env,
+        RoyaltySplit {
+            recipient: artist.clone(),
+            share: 6667,
+        },
+        RoyaltySplit {
+            recipient: gallery.clone(),
+            share: 3333,
+        },
+    ];
+    let royalty = RoyaltyInfo {
+        recipient: artist.clone(),
+        percentage: 1000,
+        splits,
+    };
+    collection_client.set_token_royalty(&admin, &2, &royalty);
+
+    // Token 1 keeps the collection royalty
+    assert_eq!(
+        collection_client.royalty_info(&1, &10_000),
+        (admin.clone(), 500)
+    );
+    assert_eq!(
+        collection_client.royalty_info(&2, &10_000),
+        (artist.clone(), 1000)
+    );
+    // Dust from the split rounding goes to the first recipient
+    assert_eq!(
+        collection_client.royalty_payouts(&2, &1_000),
+        vec![&env, (artist.clone(), 67), (gallery.clone(), 33)]
+    );
+    assert_eq!(
+        collection_client.royalty_payouts(&2, &999),
+        vec![&env, (artist.clone(), 67), (gallery.clone(), 32)]
+    );
+    assert_eq!(
+        collection_client.royalty_payouts(&1, &1_000),
+        vec![&env, (admin.clone(), 50)]
+    );
+    assert_eq!(
+        collection_client.try_royalty_info(&1, &-1),
+        Err(Ok(ContractError::InvalidAmount))
+    );
+
+    // Shares must add up to exactly 100%
+    let mut bad = royalty.clone();
+    bad.splits.set(
+        1,
+        RoyaltySplit {
+            recipient: gallery,
+            share: 3000,
+        },
+    );
+    assert_eq!(
+        collection_client.try_set_token_royalty(&admin, &2, &bad),
+        Err(Ok(ContractError::InvalidRoyalty))
+    );
+    assert_eq!(
+        collection_client.try_set_token_royalty(&admin, &3, &royalty),
+        Err(Ok(ContractError::NotFound))
+    );
+
+    collection_client.remove_token_royalty(&admin, &2);
+    assert_eq!(collection_client.royalty_info(&2, &10_000), (admin, 500));
+}
+
+#[test]
+fn test_roles_and_ownership() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, platform) = setup_collection(&env);
+    let creator = Address::generate(&env);
+    let pauser = Address::generate(&env);
+
+    // The initial owner holds every role
+    assert_eq!(collection_client.get_owner(), platform);
+    assert!(collection_client.has_role(&Role::Pauser, &platform));
+    assert!(!collection_client.has_role(&Role::Pauser, &pauser));
+    assert_eq!(
+        collection_client.try_set_pause(&pauser, &true),
+        Err(Ok(ContractError::NotAuthorized))
+    );
+
+    collection_client.grant_role(&Role::Pauser, &pauser);
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            (
+                collection_client.address.clone(),
+                (
+                    Symbol::new(&env, "role_granted"),
+                    Role::Pauser,
+                    pauser.clone()
+                )
+                    .into_val(&env),
+                platform.clone().into_val(&env),
+            )
+        ]
+    );
+    collection_client.set_pause(&pauser, &true);
+
+    collection_client.renounce_role(&Role::Pauser, &pauser);
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            (
+                collection_client.address.clone(),
+                (
+                    Symbol::new(&env, "role_revoked"),
+                    Role::Pauser,
+                    pauser.clone()
+                )
+                    .into_val(&env),
+                pauser.clone().into_val(&env),
+            )
+        ]
+    );
+    assert!(!collection_client.has_role(&Role::Pauser, &pauser));
+
+    // Ownership only moves once the new owner accepts
+    assert_eq!(
+        collection_client.try_accept_ownership(),
+        Err(Ok(ContractError::NoPendingOwner))
+    );
+    collection_client.transfer_ownership(&creator);
+    assert_eq!(collection_client.get_owner(), platform);
+    assert_eq!(collection_client.get_pending_owner(), Some(creator.clone()));
+
+    collection_client.accept_ownership();
+    assert_eq!(
+        env.auths()[0].0,
+        creator.clone(),
+        "the pending owner must authorize the handover"
+    );
+    // Role moves are announced before the handover itself
+    let events = env.events().all();
+    assert_eq!(
+        events.slice(events.len() - 1..),
+        vec![
+            &env,
+            (
+                collection_client.address.clone(),
+                (Symbol::new(&env, "ownership_transferred"), platform.clone()).into_val(&env),
+                creator.clone().into_val(&env),
+            )
+        ]
+    );
+    assert_eq!(collection_client.get_owner(), creator);
+    assert_eq!(collection_client.get_pending_owner(), None);
+
+    // The platform's roles moved to the new owner
+    assert!(!collection_client.has_role(&Role::Minter, &platform));
+    assert!(collection_client.has_role(&Role::Minter, &creator));
+    assert!(collection_client.has_role(&Role::RoyaltyAdmin, &creator));
+    let uri = String::from_str(&env, "ipfs://token");
+    assert_eq!(
+        collection_client.try_mint(&platform, &platform, &1, &uri, &Vec::new(&env), &None),
+        Err(Ok(ContractError::NotMinter))
+    );
+    assert_eq!(
+        collection_client.try_set_pause(&platform, &true),
+        Err(Ok(ContractError::NotAuthorized))
+    );
+    collection_client.mint(&creator, &creator, &1, &uri, &Vec::new(&env), &None);
+
+    // The new owner manages roles
+    collection_client.grant_role(&Role::Pauser, &platform);
+    assert_eq!(env.auths()[0].0, creator);
+    collection_client.set_pause(&platform, &true);
+}
+
+#[test]
+fn test_minter_limits() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let (collection_client, admin) = setup_collection(&env);
+    let minter = Address::generate(&env);
+    let holder = Address::generate(&env);
+    let uri = String::from_str(&env, "ipfs://token");
+
+    collection_client.grant_role(&Role::Minter, &minter);
+    collection_client.set_minter_limits(
+        &minter,
+        &MinterLimits {
+            quota: 3,
+            expires: 2_000,
+        },
+    );
+    assert_eq!(env.auths()[0].0, admin);
+
+    collection_client.mint(&minter, &holder, &1, &uri, &Vec::new(&env), &None);
+    assert_eq!(env.auths()[0].0, minter);
+    assert_eq!(
+        collection_client.get_token_metadata(&1).unwrap().creator,
+        minter
+    );
+
+    // A batch that would overshoot the quota mints nothing
+    let holders = vec![&env, holder.clone(), holder.clone(), holder.clone()];
+    let uris = vec![&env, uri.clone(), uri.clone(), uri.clone()];
+    let attributes = vec![&env, Vec::new(&env), Vec::new(&env), Vec::new(&env)];
+    assert_eq!(
+        collection_client.try_batch_mint(
+            &minter,
+            &holders,
+            &vec![&env, 2, 3, 4],
+            &uris,
+            &attributes
+        ),
+        Err(Ok(ContractError::MintLimitExceeded))
+    );
+    assert_eq!(collection_client.minted_by(&minter), 1);
+
+    collection_client.batch_mint(
+        &minter,
+        &vec![&env, holder.clone(), holder.clone()],
+        &vec![&env, 2, 3],
+        &vec![&env, uri.clone(), uri.clone()],
+        &vec![&env, Vec::new(&env), Vec::new(&env)],
+    );
+    assert_eq!(collection_client.minted_by(&minter), 3);
+    assert_eq!(
+        collection_client.try_mint(&minter, &holder, &4, &uri, &Vec::new(&env), &None),
+        Err(Ok(ContractError::MintLimitExceeded))
+    );
+
+    // Raising the quota doesn't help once the minter has expired
+    collection_client.set_minter_limits(
+        &minter,
+        &MinterLimits {
+            quota: 10,
+            expires: 2_000,
+        },
+    );
+    collection_client.mint(&minter, &holder, &4, &uri, &Vec::new(&env), &None);
+    env.ledger().set_timestamp(2_001);
+    assert_eq!(
+        collection_client.try_mint(&minter, &holder, &5, &uri, &Vec::new(&env), &None),
+        Err(Ok(ContractError::MinterExpired))
+    );
+
+    // Zeroed limits lift both caps, and the owner's own minting is unaffected
+    collection_client.set_minter_limits(&minter, &MinterLimits::default());
+    collection_client.mint(&minter, &holder, &5, &uri, &Vec::new(&env), &None);
+    collection_client.mint(&admin, &holder, &6, &uri, &Vec::new(&env), &None);
+    assert_eq!(
+        collection_client.get_token_metadata(&6).unwrap().creator,
+        admin
+    );
+}
+
+#[test]
+fn test_sequential_and_reserved_ids() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    let holder = Address::generate(&env);
+    let uri = String::from_str(&env, "ipfs://token");
+
+    // Ids start at 1 and skip reserved ranges
+    collection_client.reserve_ids(&2, &3);
+    assert_eq!(env.auths()[0].0, admin);
+    assert_eq!(
+        collection_client.try_reserve_ids(&9, &8),
+        Err(Ok(ContractError::InvalidIdRange))
+    );
+    assert_eq!(collection_client.reserved_ids(), vec![&env, (2, 3)]);
+    let mint_next = || collection_client.mint_next(&admin, &holder, &uri, &Vec::new(&env), &None);
+    assert_eq!(mint_next(), 1);
+    assert_eq!(mint_next(), 4);
+
+    // An explicit mint ahead of the cursor moves it past the minted id
+    collection_client.mint(&admin, &holder, &6, &uri, &Vec::new(&env), &None);
+    assert_eq!(mint_next(), 7);
+    assert_eq!(collection_client.owner_of(&7), holder);
+
+    // Reserved ids can still be minted explicitly
+    collection_client.mint(&admin, &holder, &2, &uri, &Vec::new(&env), &None);
+    assert_eq!(collection_client.owner_of(&2), holder);
+
+    // Burned ids can't be minted again
+    collection_client.burn(&holder, &4);
+    assert!(collection_client.is_burned(&4));
+    assert_eq!(
+        collection_client.try_mint(&admin, &holder, &4, &uri, &Vec::new(&env), &None),
+        Err(Ok(ContractError::TokenBurned))
+    );
+    assert_eq!(mint_next(), 8);
+
+    // Released ranges are handed out again
+    collection_client.reserve_ids(&9, &10);
+    collection_client.unreserve_ids(&9, &10);
+    assert_eq!(
+        collection_client.try_unreserve_ids(&9, &10),
+        Err(Ok(ContractError::InvalidIdRange))
+    );
+    assert_eq!(collection_client.reserved_ids(), vec![&env, (2, 3)]);
+    assert_eq!(mint_next(), 9);
+
+    // The number of reserved ranges is capped
+    for start in 1..16 {
+        collection_client.reserve_ids(&(start * 100), &(start * 100));
+    }
+    assert_eq!(
+        collection_client.try_reserve_ids(&5_000, &5_000),
+        Err(Ok(ContractError::TooManyReservedRanges))
+    );
+    collection_client.unreserve_ids(&2, &3);
+
+    // The last id is handed out once, then auto-assignment runs dry
+    collection_client.reserve_ids(&10, &(u32::MAX - 1));
+    assert_eq!(mint_next(), u32::MAX);
+    assert_eq!(
+        collection_client.try_mint_next(&admin, &holder, &uri, &Vec::new(&env), &None),
+        Err(Ok(ContractError::SupplyLimitExceeded))
+    );
+}
+
+#[test]
+fn test_soulbound() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_sequence_number(100);
+
+    let issuer = Address::generate(&env);
+    let holder = Address::generate(&env);
+    let other = Address::generate(&env);
+
+    let collection_client = NftCollectionClient::new(&env, &env.register(NftCollection, ()));
+    let mut config = collection_config(&env, &issuer);
+    config.soulbound = true;
+    config.revocable = true;
+    collection_client.init(&issuer, &config);
+    assert!(collection_client.is_soulbound());
+
+    let uri = String::from_str(&env, "ipfs://badge");
+    collection_client.mint(&issuer, &holder, &1, &uri, &Vec::new(&env), &None);
+    collection_client.mint(&issuer, &holder, &2, &uri, &Vec::new(&env), &None);
+    collection_client.mint(&issuer, &holder, &3, &uri, &Vec::new(&env), &None);
+
+    assert_eq!(
+        collection_client.try_transfer(&holder, &other, &1),
+        Err(Ok(ContractError::Soulbound))
+    );
+    assert_eq!(
+        collection_client.try_batch_transfer(&holder, &other, &vec![&env, 1]),
+        Err(Ok(ContractError::Soulbound))
+    );
+    assert_eq!(
+        collection_client.try_approve(&holder, &other, &1, &200),
+        Err(Ok(ContractError::Soulbound))
+    );
+    assert_eq!(
+        collection_client.try_approve_for_all(&holder, &other, &200),
+        Err(Ok(ContractError::Soulbound))
+    );
+    assert_eq!(
+        collection_client.try_set_approval_for_all(&holder, &other, &true),
+        Err(Ok(ContractError::Soulbound))
+    );
+    assert_eq!(collection_client.owner_of(&1), holder);
+
+    // The holder can still burn, and the issuer can revoke
+    collection_client.burn(&holder, &1);
+    collection_client.revoke_token(&2);
+    assert_eq!(env.auths()[0].0, issuer);
+    assert_eq!(
+        collection_client.try_owner_of(&2),
+        Err(Ok(ContractError::NotFound))
+    );
+    assert_eq!(collection_client.balance(&holder), 1);
+    assert_eq!(collection_client.total_supply(), 1);
+
+    // Revoking needs a soulbound, revocable collection
+    let (transferable, transferable_admin) = setup_collection(&env);
+    transferable.mint(
+        &transferable_admin,
+        &holder,
+        &1,
+        &uri,
+        &Vec::new(&env),
+        &None,
+    );
+    assert!(!transferable.is_soulbound());
+    assert_eq!(
+        transferable.try_revoke_token(&1),
+        Err(Ok(ContractError::NotAuthorized))
+    );
+}
+
+#[contract]
+struct AcceptingReceiver;
+
+#[contractimpl]
+impl AcceptingReceiver {
+    pub fn on_nft_received(
+        env: Env,
+        operator: Address,
+        from: Option<Address>,
+        token_id: u32,
+        data: Bytes,
+    ) -> Symbol {
+        env.storage().instance().set(
+            &symbol_short!("received"),
+            &(operator, from, token_id, data),
+        );
+        Symbol::new(&env, "on_nft_received")
+    }
+
+    pub fn received(env: Env) -> Option<(Address, Option<Address>, u32, Bytes)> {
+        env.storage().instance().get(&symbol_short!("received"))
+    }
+}
+
+#[contract]
+struct RejectingReceiver;
+
+#[contractimpl]
+impl RejectingReceiver {
+    pub fn on_nft_received(
+        _env: Env,
+        _operator: Address,
+        _from: Option<Address>,
+        _token_id: u32,
+        _data: Bytes,
+    ) -> Symbol {
+        symbol_short!("nope")
+    }
+}
+
+#[test]
+fn test_safe_transfer_and_mint() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    let owner = Address::generate(&env);
+    let operator = Address::generate(&env);
+    let accepting = AcceptingReceiverClient::new(&env, &env.register(AcceptingReceiver, ()));
+    let rejecting = env.register(RejectingReceiver, ());
+    // A contract without the hook, like another collection
+    let (no_hook, _) = setup_collection(&env);
+
+    let uri = String::from_str(&env, "ipfs://hash");
+    let data = Bytes::from_slice(&env, b"hello");
+
+    // Accounts keep working unchanged
+    collection_client.safe_mint(&admin, &owner, &1, &uri, &Vec::new(&env), &None, &data);
+    collection_client.safe_transfer(&owner, &operator, &1, &data);
+    assert_eq!(collection_client.owner_of(&1), operator);
+
+    collection_client.safe_transfer(&operator, &accepting.address, &1, &data);
+    assert_eq!(collection_client.owner_of(&1), accepting.address);
+    assert_eq!(
+        accepting.received(),
+        Some((operator.clone(), Some(operator.clone()), 1, data.clone()))
+    );
+
+    collection_client.safe_mint(
+        &admin,
+        &accepting.address,
+        &2,
+        &uri,
+        &Vec::new(&env),
+        &None,
+        &data,
+    );
+    assert_eq!(
+        accepting.received(),
+        Some((admin.clone(), None, 2, data.clone()))
+    );
+
+    // Rejections revert the whole transfer
+    collection_client.mint(&admin, &owner, &3, &uri, &Vec::new(&env), &None);
+    collection_client.approve(&owner, &operator, &3, &1000);
+    assert_eq!(
+        collection_client.try_safe_transfer_from(&operator, &owner, &rejecting, &3, &data),
+        Err(Ok(ContractError::ReceiverRejected))
+    );
+    assert_eq!(
+        collection_client.try_safe_transfer(&owner, &no_hook.address, &3, &data),
+        Err(Ok(ContractError::ReceiverRejected))
+    );
+    assert_eq!(
+        collection_client.try_safe_mint(
+            &admin,
+            &rejecting,
+            &4,
+            &uri,
+            &Vec::new(&env),
+            &None,
+            &data
+        ),
+        Err(Ok(ContractError::ReceiverRejected))
+    );
+    assert_eq!(collection_client.owner_of(&3), owner);
+    assert_eq!(collection_client.get_approved(&3), Some(operator.clone()));
+    assert_eq!(
+        collection_client.try_owner_of(&4),
+        Err(Ok(ContractError::NotFound))
+    );
+
+    collection_client.safe_transfer_from(&operator, &owner, &accepting.address, &3, &data);
+    assert_eq!(accepting.received(), Some((operator, Some(owner), 3, data)));
+}
+
+#[test]
+fn test_operator_policy() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_sequence_number(100);
+
+    let (collection_client, admin) = setup_collection(&env);
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let marketplace = Address::generate(&env);
+    let other_market = Address::generate(&env);
+
+    let uri = String::from_str(&env, "ipfs://hash");
+    collection_client.mint(&admin, &owner, &1, &uri, &Vec::new(&env), &None);
+    collection_client.mint(&admin, &owner, &2, &uri, &Vec::new(&env), &None);
+    // Granted before the policy is switched on
+    collection_client.approve(&owner, &other_market, &2, &1000);
+
+    collection_client.set_allowed_operator(&marketplace, &true);
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            (
+                collection_client.address.clone(),
+                (Symbol::new(&env, "operator_allowed"), marketplace.clone()).into_val(&env),
+                true.into_val(&env),
+            )
+        ]
+    );
+    assert!(collection_client.is_allowed_operator(&marketplace));
+
+    collection_client.set_operator_policy(&OperatorPolicy {
+        restrict_approvals: true,
+        restrict_transfers: false,
+    });
+
+    assert_eq!(
+        collection_client.try_approve(&owner, &other_market, &1, &1000),
+        Err(Ok(ContractError::OperatorNotAllowed))
+    );
+    assert_eq!(
+        collection_client.try_set_approval_for_all(&owner, &other_market, &true),
+        Err(Ok(ContractError::OperatorNotAllowed))
+    );
+    assert_eq!(
+        collection_client.try_transfer_from(&other_market, &owner, &buyer, &2),
+        Err(Ok(ContractError::OperatorNotAllowed))
+    );
+
+    collection_client.approve(&owner, &marketplace, &1, &1000);
+    collection_client.transfer_from(&marketplace, &owner, &buyer, &1);
+    assert_eq!(collection_client.owner_of(&1), buyer);
+
+    // Direct transfers stay open until restricted too
+    collection_client.transfer(&buyer, &owner, &1);
+    collection_client.set_operator_policy(&OperatorPolicy {
+        restrict_approvals: true,
+        restrict_transfers: true,
+    });
+    assert_eq!(
+        collection_client.try_transfer(&owner, &buyer, &1),
+        Err(Ok(ContractError::TransferRestricted))
+    );
+    assert_eq!(
+        collection_client.try_batch_transfer(&owner, &buyer, &vec![&env, 1]),
+        Err(Ok(ContractError::TransferRestricted))
+    );
+
+    // Moving in and out of an allowed escrow is fine
+    collection_client.transfer(&owner, &marketplace, &1);
+    collection_client.transfer(&marketplace, &buyer, &1);
+    assert_eq!(collection_client.owner_of(&1), buyer);
+
+    collection_client.set_allowed_operator(&marketplace, &false);
+    assert_eq!(
+        collection_client.try_transfer(&buyer, &marketplace, &1),
+        Err(Ok(ContractError::TransferRestricted))
+    );
+    // Open approvals don't open a way around transfer restrictions
+    collection_client.set_operator_policy(&OperatorPolicy {
+        restrict_approvals: false,
+        restrict_transfers: true,
+    });
+    collection_client.approve(&owner, &other_market, &2, &1000);
+    assert_eq!(
+        collection_client.try_transfer_from(&other_market, &owner, &buyer, &2),
+        Err(Ok(ContractError::TransferRestricted))
+    );
+}
+
+#[test]
+fn test_token_locking() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_sequence_number(100);
+
+    let (collection_client, admin) = setup_collection(&env);
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let lender = Address::generate(&env);
+    let other_locker = Address::generate(&env);
+
+    let uri = String::from_str(&env, "ipfs://hash");
+    collection_client.mint(&admin, &owner, &1, &uri, &Vec::new(&env), &None);
+    collection_client.approve(&owner, &lender, &1, &1000);
+
+    // Only lockers approved by the collection owner can lock
+    assert_eq!(
+        collection_client.try_lock(&lender, &1),
+        Err(Ok(ContractError::NotAuthorized))
+    );
+    collection_client.set_locker(&lender, &true);
+    collection_client.set_locker(&other_locker, &true);
+    // ...and only tokens they hold or are approved for
+    assert_eq!(
+        collection_client.try_lock(&other_locker, &1),
+        Err(Ok(ContractError::NotApproved))
+    );
+
+    collection_client.lock(&lender, &1);
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            (
+                collection_client.address.clone(),
+                (Symbol::new(&env, "lock"), lender.clone()).into_val(&env),
+                1u32.into_val(&env),
+            )
+        ]
+    );
+    assert!(collection_client.is_locked(&1));
+    assert_eq!(collection_client.locked_by(&1), Some(lender.clone()));
+
+    assert_eq!(
+        collection_client.try_transfer(&owner, &buyer, &1),
+        Err(Ok(ContractError::TokenLocked))
+    );
+    assert_eq!(
+        collection_client.try_transfer_from(&lender, &owner, &buyer, &1),
+        Err(Ok(ContractError::TokenLocked))
+    );
+    assert_eq!(
+        collection_client.try_batch_transfer(&owner, &buyer, &vec![&env, 1]),
+        Err(Ok(ContractError::TokenLocked))
+    );
+    assert_eq!(
+        collection_client.try_burn(&owner, &1),
+        Err(Ok(ContractError::TokenLocked))
+    );
+    assert_eq!(
+        collection_client.try_lock(&lender, &1),
+        Err(Ok(ContractError::TokenLocked))
+    );
+
+    // Only the same locker can unlock
+    assert_eq!(
+        collection_client.try_unlock(&other_locker, &1),
+        Err(Ok(ContractError::NotAuthorized))
+    );
+    collection_client.unlock(&lender, &1);
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            (
+                collection_client.address.clone(),
+                (Symbol::new(&env, "unlock"), lender.clone()).into_val(&env),
+                1u32.into_val(&env),
+            )
+        ]
+    );
+    assert!(!collection_client.is_locked(&1));
+    assert_eq!(collection_client.locked_by(&1), None);
+
+    collection_client.transfer_from(&lender, &owner, &buyer, &1);
+    assert_eq!(collection_client.owner_of(&1), buyer);
+}
+
+#[test]
+fn test_rentals() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_sequence_number(100);
+    env.ledger().set_timestamp(1_000);
+
+    let (collection_client, admin) = setup_collection(&env);
+    let owner = Address::generate(&env);
+    let operator = Address::generate(&env);
+    let player = Address::generate(&env);
+    let stranger = Address::generate(&env);
+
+    let uri = String::from_str(&env, "ipfs://hash");
+    collection_client.mint(&admin, &owner, &1, &uri, &Vec::new(&env), &None);
+    assert_eq!(collection_client.user_of(&1), None);
+
+    assert_eq!(
+        collection_client.try_set_user(&stranger, &1, &Some(player.clone()), &2_000),
+        Err(Ok(ContractError::NotApproved))
+    );
+
+    collection_client.set_approval_for_all(&owner, &operator, &true);
+    collection_client.set_user(&operator, &1, &Some(player.clone()), &2_000);
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            (
+                collection_client.address.clone(),
+                (Symbol::new(&env, "update_user"), 1u32).into_val(&env),
+                (Some(player.clone()), 2_000u64).into_val(&env),
+            )
+        ]
+    );
+    assert_eq!(collection_client.user_of(&1), Some(player.clone()));
+    assert_eq!(collection_client.user_expires(&1), 2_000);
+    // Renting doesn't move the token
+    assert_eq!(collection_client.owner_of(&1), owner);
+
+    env.ledger().set_timestamp(2_000);
+    assert_eq!(collection_client.user_of(&1), None);
+
+    // A transfer ends the rental
+    collection_client.set_user(&owner, &1, &Some(player.clone()), &3_000);
+    collection_client.transfer(&owner, &stranger, &1);
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            (
+                collection_client.address.clone(),
+                (Symbol::new(&env, "update_user"), 1u32).into_val(&env),
+                (None::<Address>, 0u64).into_val(&env),
+            ),
+            (
+                collection_client.address.clone(),
+                (
+                    Symbol::new(&env, "transfer"),
+                    owner.clone(),
+                    stranger.clone()
+                )
+                    .into_val(&env),
+                1u32.into_val(&env),
+            )
+        ]
+    );
+    assert_eq!(collection_client.user_of(&1), None);
+    assert_eq!(collection_client.user_expires(&1), 0);
+
+    collection_client.set_user(&stranger, &1, &Some(player), &3_000);
+    collection_client.set_user(&stranger, &1, &None, &0);
+    assert_eq!(collection_client.user_of(&1), None);
+}
+
+#[test]
+fn test_enumeration() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (collection_client, admin) = setup_collection(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+
+    let uri = String::from_str(&env, "ipfs://hash");
+    for token_id in [10, 20, 30, 40] {
+        collection_client.mint(&admin, &alice, &token_id, &uri, &Vec::new(&env), &None);
+    }
+    collection_client.batch_mint(
+        &admin,
+        &vec![&env, bob.clone(), bob.clone()],
+        &vec![&env, 50, 60],
+        &vec![&env, uri.clone(), uri.clone()],
+        &vec![&env, Vec::new(&env), Vec::new(&env)],
+    );
+
+    assert_eq!(
+        collection_client.token_ids(&0, &10),
+        vec![&env, 10, 20, 30, 40, 50, 60]
+    );
+    assert_eq!(collection_client.token_ids(&4, &10), vec![&env, 50, 60]);
+    assert_eq!(collection_client.token_ids(&2, &1), vec![&env, 30]);
+    assert_eq!(
+        collection_client.tokens_of_owner(&alice, &0, &10),
+        vec![&env, 10, 20, 30, 40]
+    );
+    assert_eq!(
+        collection_client.tokens_of_owner(&bob, &1, &10),
+        vec![&env, 60]
+    );
+
+    // Removals move the last token into the freed slot
+    collection_client.transfer(&alice, &bob, &20);
+    collection_client.batch_transfer(&bob, &alice, &vec![&env, 50]);
+    assert_eq!(
+        collection_client.tokens_of_owner(&alice, &0, &10),
+        vec![&env, 10, 40, 30, 50]
+    );
+    assert_eq!(
+        collection_client.tokens_of_owner(&bob, &0, &10),
+        vec![&env, 20, 60]
+    );
+
+    collection_client.burn(&alice, &10);
+    assert_eq!(
+        collection_client.token_ids(&0, &10),
+        vec![&env, 60, 20, 30, 40, 50]
+    );
+    assert_eq!(
+        collection_client.tokens_of_owner(&alice, &0, &10),
+        vec![&env, 50, 40, 30]
+    );
+    assert_eq!(collection_client.token_by_index(&0), 60);
+    assert_eq!(
+        collection_client.try_token_by_index(&5),
+        Err(Ok(ContractError::NotFound))
+    );
+
+    for token_id in [20, 60] {
+        collection_client.burn(&bob, &token_id);
+    }
+    assert_eq!(
+        collection_client.tokens_of_owner(&bob, &0, &10),
+        Vec::new(&env)
+    );
+    assert_eq!(collection_client.balance(&bob), 0);
+    assert_eq!(collection_client.total_supply(), 3);
+}
+
+#[test]
+fn test_snapshots() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let (collection_client, admin) = setup_collection(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+
+    let uri = String::from_str(&env, "ipfs://hash");
+    collection_client.mint(&admin, &alice, &1, &uri, &Vec::new(&env), &None);
+    collection_client.mint(&admin, &alice, &2, &uri, &Vec::new(&env), &None);
+
+    assert_eq!(
+        collection_client.try_owner_at(&1, &1),
+        Err(Ok(ContractError::InvalidSnapshot))
+    );
+
+    assert_eq!(collection_client.snapshot(), 1);
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            (
+                collection_client.address.clone(),
+                (Symbol::new(&env, "snapshot"), 1u32).into_val(&env),
+                1_000u64.into_val(&env),
+            )
+        ]
+    );
+
+    collection_client.transfer(&alice, &bob, &1);
+    collection_client.transfer(&bob, &alice, &1);
+    collection_client.transfer(&alice, &bob, &1);
+    collection_client.mint(&admin, &bob, &3, &uri, &Vec::new(&env), &None);
+
+    assert_eq!(collection_client.snapshot(), 2);
+    collection_client.burn(&alice, &2);
+    assert_eq!(collection_client.snapshot(), 3);
+
+    // Snapshot 1: before any transfer
+    assert_eq!(collection_client.owner_at(&1, &1), alice);
+    assert_eq!(collection_client.balance_at(&alice, &1), 2);
+    assert_eq!(collection_client.balance_at(&bob, &1), 0);
+    assert_eq!(
+        collection_client.try_owner_at(&3, &1),
+        Err(Ok(ContractError::NotFound))
+    );
+
+    // Snapshot 2: token 1 moved, token 3 minted
+    assert_eq!(collection_client.owner_at(&1, &2), bob);
+    assert_eq!(collection_client.owner_at(&2, &2), alice);
+    assert_eq!(collection_client.owner_at(&3, &2), bob);
+    assert_eq!(collection_client.balance_at(&alice, &2), 1);
+    assert_eq!(collection_client.balance_at(&bob, &2), 2);
+
+    // Snapshot 3: token 2 burned
+    assert_eq!(
+        collection_client.try_owner_at(&2, &3),
+        Err(Ok(ContractError::NotFound))
+    );
+    assert_eq!(collection_client.balance_at(&alice, &3), 0);
+    assert_eq!(
+        collection_client.try_balance_at(&alice, &4),
+        Err(Ok(ContractError::InvalidSnapshot))
+    );
+}
+
+#[test]
+fn test_provenance() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let (collection_client, admin) = setup_collection(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let carol = Address::generate(&env);
+    let marketplace = Address::generate(&env);
+    let payment_token = Address::generate(&env);
+    let uri = String::from_str(&env, "ipfs://token");
+    let entry = |operator: &Address, from: Option<&Address>, to: Option<&Address>, timestamp| {
+        ProvenanceEntry {
+            operator: operator.clone(),
+            from: from.cloned(),
+            to: to.cloned(),
+            timestamp,
+            price: None,
+            payment_token: None,
+        }
+    };
+
+    collection_client.mint(&admin, &alice, &1, &uri, &Vec::new(&env), &None);
+    env.ledger().set_timestamp(2_000);
+    collection_client.transfer(&alice, &bob, &1);
+    collection_client.approve(&bob, &carol, &1, &(env.ledger().sequence() + 100));
+    collection_client.transfer_from(&carol, &bob, &marketplace, &1);
+
+    // Only trusted marketplaces report prices
+    assert_eq!(
+        collection_client.try_report_sale(&marketplace, &1, &payment_token, &5_000),
+        Err(Ok(ContractError::NotAuthorized))
+    );
+    collection_client.set_trusted_marketplace(&marketplace, &true);
+    assert_eq!(env.auths()[0].0, admin);
+    assert!(collection_client.is_trusted_marketplace(&marketplace));
+
+    // The price is logged with the delivery the marketplace makes
+    env.ledger().set_timestamp(3_000);
+    collection_client.report_sale(&marketplace, &1, &payment_token, &5_000);
+    collection_client.transfer(&marketplace, &alice, &1);
+
+    // ...and not with a transfer by anyone else
+    collection_client.report_sale(&marketplace, &1, &payment_token, &7_000);
+    collection_client.transfer(&alice, &bob, &1);
+    collection_client.burn(&bob, &1);
+
+    let mut sale = entry(&marketplace, Some(&marketplace), Some(&alice), 3_000);
+    sale.price = Some(5_000);
+    sale.payment_token = Some(payment_token.clone());
+    assert_eq!(collection_client.provenance_len(&1), 6);
+    assert_eq!(
+        collection_client.provenance(&1, &0, &10),
+        vec![
+            &env,
+            entry(&admin, None, Some(&alice), 1_000),
+            entry(&alice, Some(&alice), Some(&bob), 2_000),
+            entry(&carol, Some(&bob), Some(&marketplace), 2_000),
+            sale,
+            entry(&alice, Some(&alice), Some(&bob), 3_000),
+            entry(&bob, Some(&bob), None, 3_000),
+        ]
+    );
+
+    // Pages stop at the end of the log
+    assert_eq!(
+        collection_client.provenance(&1, &4, &10),
+        vec![
+            &env,
+            entry(&alice, Some(&alice), Some(&bob), 3_000),
+            entry(&bob, Some(&bob), None, 3_000),
+        ]
+    );
+    assert_eq!(collection_client.provenance(&1, &6, &10), Vec::new(&env));
+    assert_eq!(
+        collection_client.try_report_sale(&marketplace, &1, &payment_token, &5_000),
+        Err(Ok(ContractError::NotFound))
+    );
 }
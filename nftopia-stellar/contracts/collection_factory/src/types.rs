@@ -1,4 +1,4 @@
-use soroban_sdk::{Address, String, Vec, contracttype};
+use soroban_sdk::{Address, BytesN, String, Vec, contracttype};
 
 #[derive(Clone, Debug)]
 #[contracttype]
@@ -11,6 +11,10 @@ pub struct CollectionConfig {
     pub is_public_mint: bool,
     pub royalty_percentage: u32, // Basis points (100 = 1%)
     pub royalty_recipient: Address,
+    pub placeholder_uri: Option<String>, // Served until reveal; None = revealed
+    pub provenance_hash: Option<BytesN<32>>,
+    pub soulbound: bool, // Tokens stay with their first holder
+    pub revocable: bool, // Soulbound tokens can be revoked by the owner
 }
 
 #[derive(Clone, Debug)]
@@ -21,6 +25,70 @@ pub struct TokenMetadata {
     pub attributes: Vec<(String, String)>,
     pub creator: Address,
     pub created_at: u64,
+    pub content_hash: Option<BytesN<32>>, // sha256 of the media behind `uri`
+}
+
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct TokenApproval {
+    pub approved: Address,
+    pub live_until_ledger: u32,
+}
+
+/// Creator policy limiting who can move tokens, so trades go through
+/// marketplaces that pay royalties.
+#[derive(Clone, Debug, Default)]
+#[contracttype]
+pub struct OperatorPolicy {
+    pub restrict_approvals: bool, // Only allowed operators act on approvals
+    pub restrict_transfers: bool, // Direct transfers must involve an allowed operator
+}
+
+/// Privileges the collection owner can hand out. The owner itself is
+/// tracked separately and changes hands through a two-step transfer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Role {
+    Minter,
+    Pauser,
+    MetadataAdmin,
+    RoyaltyAdmin,
+}
+
+/// Per-token logs that grow with every change, extended a page at a time
+/// by `extend_history_ttl`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum TokenHistory {
+    Uri,
+    Owner,
+    Provenance,
+}
+
+#[derive(Clone, Debug, Default)]
+#[contracttype]
+pub struct MinterLimits {
+    pub quota: u32,   // Total tokens the minter may mint, 0 = unlimited
+    pub expires: u64, // Last timestamp the minter may mint, 0 = never
+}
+
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct UserInfo {
+    pub user: Address,
+    pub expires: u64, // Timestamp the rental ends at
+}
+
+/// One custody change in a token's provenance log.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ProvenanceEntry {
+    pub operator: Address,     // Who moved the token; the minter on mint
+    pub from: Option<Address>, // None on mint
+    pub to: Option<Address>,   // None on burn
+    pub timestamp: u64,
+    pub price: Option<i128>, // Sale price reported by a trusted marketplace
+    pub payment_token: Option<Address>, // Token `price` was paid in
 }
 
 #[derive(Clone, Debug)]
@@ -37,5 +105,47 @@ pub struct CollectionInfo {
 #[contracttype]
 pub struct RoyaltyInfo {
     pub recipient: Address,
-    pub percentage: u32, // Basis points
+    pub percentage: u32,           // Basis points
+    pub splits: Vec<RoyaltySplit>, // Empty = everything to `recipient`
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RoyaltySplit {
+    pub recipient: Address,
+    pub share: u32, // Basis points of the royalty, all shares sum to 10000
+}
+
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct PublicMintConfig {
+    pub payment_token: Address, // SAC the mint price is paid in
+    pub price: i128,
+    pub wallet_limit: u32, // 0 = unlimited
+    pub payout_address: Address,
+}
+
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct MintPhase {
+    pub start_time: u64,
+    pub end_time: u64, // Exclusive
+    pub price: i128,
+    pub allocation: u32, // Per address, 0 = unlimited
+    pub allowlist: bool,
+    pub merkle_root: Option<BytesN<32>>,
+}
+
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct MintVoucher {
+    pub token_id: u32,
+    pub uri: String,
+    pub attributes: Vec<(String, String)>,
+    pub content_hash: Option<BytesN<32>>,
+    pub price: i128,
+    pub payment_token: Address, // SAC `price` is paid in
+    pub payout: Address,        // Receives `price`
+    pub expiry: u64,            // Last valid timestamp
+    pub nonce: u64,
 }
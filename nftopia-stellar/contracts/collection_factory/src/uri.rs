@@ -0,0 +1,172 @@
+use soroban_sdk::{Bytes, Env, String, Vec};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Appends the decimal `token_id` to `base_uri`.
+pub fn join(base_uri: &String, token_id: u32) -> String {
+    let mut bytes = base_uri.to_bytes();
+    push_decimal(&mut bytes, token_id);
+    bytes.into()
+}
+
+/// Builds a `data:application/json;base64,` metadata document for a token
+/// following the common NFT metadata schema. With an `svg_template`, the
+/// rendered SVG is embedded as the image.
+pub fn json_data_uri(
+    env: &Env,
+    name: &String,
+    description: &String,
+    token_id: u32,
+    attributes: &Vec<(String, String)>,
+    svg_template: Option<String>,
+) -> String {
+    let mut json = Bytes::from_slice(env, b"{\"name\":\"");
+    push_json_escaped(&mut json, &name.to_bytes());
+    json.extend_from_slice(b" #");
+    push_decimal(&mut json, token_id);
+    json.extend_from_slice(b"\",\"description\":\"");
+    push_json_escaped(&mut json, &description.to_bytes());
+    json.push_back(b'"');
+
+    if let Some(template) = svg_template {
+        let svg = render_svg(env, &template.to_bytes(), name, token_id, attributes);
+        json.extend_from_slice(b",\"image\":\"data:image/svg+xml;base64,");
+        json.append(&base64(env, &svg));
+        json.push_back(b'"');
+    }
+
+    json.extend_from_slice(b",\"attributes\":[");
+    for (i, (trait_type, value)) in attributes.iter().enumerate() {
+        if i > 0 {
+            json.push_back(b',');
+        }
+        json.extend_from_slice(b"{\"trait_type\":\"");
+        push_json_escaped(&mut json, &trait_type.to_bytes());
+        json.extend_from_slice(b"\",\"value\":\"");
+        push_json_escaped(&mut json, &value.to_bytes());
+        json.extend_from_slice(b"\"}");
+    }
+    json.extend_from_slice(b"]}");
+
+    let mut uri = Bytes::from_slice(env, b"data:application/json;base64,");
+    uri.append(&base64(env, &json));
+    uri.into()
+}
+
+/// Replaces `{id}`, `{name}` and `{<trait_type>}` placeholders in `template`
+/// with the token id, collection name and attribute values. Unknown
+/// placeholders render empty.
+fn render_svg(
+    env: &Env,
+    template: &Bytes,
+    name: &String,
+    token_id: u32,
+    attributes: &Vec<(String, String)>,
+) -> Bytes {
+    let mut svg = Bytes::new(env);
+    let mut placeholder: Option<Bytes> = None;
+    for byte in template.iter() {
+        match (&mut placeholder, byte) {
+            (None, b'{') => placeholder = Some(Bytes::new(env)),
+            (None, _) => svg.push_back(byte),
+            (Some(key), b'}') => {
+                if *key == Bytes::from_slice(env, b"id") {
+                    push_decimal(&mut svg, token_id);
+                } else if *key == Bytes::from_slice(env, b"name") {
+                    push_xml_escaped(&mut svg, &name.to_bytes());
+                } else if let Some((_, value)) = attributes
+                    .iter()
+                    .find(|(trait_type, _)| trait_type.to_bytes() == *key)
+                {
+                    push_xml_escaped(&mut svg, &value.to_bytes());
+                }
+                placeholder = None;
+            }
+            (Some(key), _) => key.push_back(byte),
+        }
+    }
+    // An unterminated placeholder is kept as written
+    if let Some(key) = placeholder {
+        svg.push_back(b'{');
+        svg.append(&key);
+    }
+    svg
+}
+
+fn push_decimal(out: &mut Bytes, value: u32) {
+    let mut digits = [0u8; 10];
+    let mut start = digits.len();
+    let mut value = value;
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    out.extend_from_slice(&digits[start..]);
+}
+
+fn push_json_escaped(out: &mut Bytes, value: &Bytes) {
+    for byte in value.iter() {
+        match byte {
+            b'"' => out.extend_from_slice(b"\\\""),
+            b'\\' => out.extend_from_slice(b"\\\\"),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            b'\r' => out.extend_from_slice(b"\\r"),
+            b'\t' => out.extend_from_slice(b"\\t"),
+            0..=0x1f => {
+                let hex = b"0123456789abcdef";
+                out.extend_from_slice(&[
+                    b'\\',
+                    b'u',
+                    b'0',
+                    b'0',
+                    hex[(byte >> 4) as usize],
+                    hex[(byte & 0xf) as usize],
+                ]);
+            }
+            _ => out.push_back(byte),
+        }
+    }
+}
+
+fn push_xml_escaped(out: &mut Bytes, value: &Bytes) {
+    for byte in value.iter() {
+        match byte {
+            b'&' => out.extend_from_slice(b"&amp;"),
+            b'<' => out.extend_from_slice(b"&lt;"),
+            b'>' => out.extend_from_slice(b"&gt;"),
+            b'"' => out.extend_from_slice(b"&quot;"),
+            b'\'' => out.extend_from_slice(b"&apos;"),
+            _ => out.push_back(byte),
+        }
+    }
+}
+
+fn base64(env: &Env, input: &Bytes) -> Bytes {
+    let mut out = Bytes::new(env);
+    let mut bytes = input.iter();
+    while let Some(first) = bytes.next() {
+        let second = bytes.next();
+        let third = bytes.next();
+        let n =
+            (first as u32) << 16 | (second.unwrap_or(0) as u32) << 8 | third.unwrap_or(0) as u32;
+        out.extend_from_slice(&[
+            BASE64[(n >> 18) as usize & 63],
+            BASE64[(n >> 12) as usize & 63],
+            if second.is_some() {
+                BASE64[(n >> 6) as usize & 63]
+            } else {
+                b'='
+            },
+            if third.is_some() {
+                BASE64[n as usize & 63]
+            } else {
+                b'='
+            },
+        ]);
+    }
+    out
+}
@@ -1,8 +1,7 @@
 use soroban_sdk::{Env, Address, Vec, Map, Symbol, contracttype, symbol_short, Bytes};
 use crate::error::SettlementError;
 use crate::types::{Asset, ExecutionResult};
-use crate::utils::asset_utils;
-use crate::security::reentrancy_guard::ReentrancyGuard;
+use crate::utils::{asset_utils, math_utils};
 
 // Storage keys
 const ATOMIC_SWAPS: Symbol = symbol_short!("atom_swps");
@@ -16,7 +15,7 @@ pub struct EscrowHolding {
     pub asset: Asset,
     pub amount: i128, // For tokens, or token_id for NFTs
     pub is_nft: bool,
-    pub deposited_at: u64,
+    pub deposited_at: Option<u64>, // None until actually deposited
     pub released_at: Option<u64>,
 }
 
@@ -71,7 +70,7 @@ impl AtomicSwapEngine {
             },
             amount: token_id as i128,
             is_nft: true,
-            deposited_at: env.ledger().timestamp(),
+            deposited_at: None,
             released_at: None,
         });
 
@@ -82,7 +81,7 @@ impl AtomicSwapEngine {
             asset: payment_asset.clone(),
             amount: payment_amount,
             is_nft: false,
-            deposited_at: env.ledger().timestamp(),
+            deposited_at: None,
             released_at: None,
         });
 
@@ -100,6 +99,28 @@ impl AtomicSwapEngine {
         Ok(swap_id)
     }
 
+    /// Assign the buyer of a swap that was initialized before the buyer was known
+    pub fn set_buyer(
+        env: &Env,
+        transaction_id: u64,
+        buyer: &Address
+    ) -> Result<(), SettlementError> {
+        let mut swap = Self::get_swap_by_transaction(env, transaction_id)?;
+
+        for i in 0..swap.buyer_escrow.len() {
+            if let Some(mut holding) = swap.buyer_escrow.get(i) {
+                // Funds already in escrow stay attributed to whoever deposited them
+                if holding.deposited_at.is_some() {
+                    return Err(SettlementError::InvalidState);
+                }
+                holding.holder = buyer.clone();
+                swap.buyer_escrow.set(i, holding);
+            }
+        }
+
+        Self::store_swap(env, &swap)
+    }
+
     /// Deposit funds/NFTs into escrow
     pub fn deposit_to_escrow(
         env: &Env,
@@ -133,37 +154,39 @@ impl AtomicSwapEngine {
     }
 
     /// Execute the atomic swap
+    ///
+    /// `withheld` is the part of the payment that stays in escrow for royalties
+    /// and platform fees; the seller receives the remainder. Callers are expected
+    /// to hold the reentrancy guard already.
     pub fn execute_swap(
         env: &Env,
         transaction_id: u64,
-        executor: &Address
+        withheld: i128
     ) -> Result<ExecutionResult, SettlementError> {
-        ReentrancyGuard::execute(env, executor, "execute_swap", || {
-            let mut swap = Self::get_swap_by_transaction(env, transaction_id)?;
+        let mut swap = Self::get_swap_by_transaction(env, transaction_id)?;
 
-            // Validate swap is ready for execution
-            if swap.state != SwapState::Ready {
-                return Err(SettlementError::InvalidState);
-            }
+        // Validate swap is ready for execution
+        if swap.state != SwapState::Ready {
+            return Err(SettlementError::InvalidState);
+        }
 
-            // Perform the atomic swap
-            Self::perform_atomic_swap(env, &swap)?;
+        // Perform the atomic swap
+        Self::perform_atomic_swap(env, &swap, withheld)?;
 
-            // Update swap state
-            swap.state = SwapState::Executed;
-            swap.executed_at = Some(env.ledger().timestamp());
+        // Update swap state
+        swap.state = SwapState::Executed;
+        swap.executed_at = Some(env.ledger().timestamp());
 
-            Self::store_swap(env, &swap)?;
+        Self::store_swap(env, &swap)?;
 
-            Ok(ExecutionResult {
-                transaction_id,
-                success: true,
-                transferred_nft: true,
-                transferred_payment: true,
-                distributed_royalties: true, // This would be handled by royalty system
-                collected_platform_fee: true, // This would be handled by fee system
-                timestamp: env.ledger().timestamp(),
-            })
+        Ok(ExecutionResult {
+            transaction_id,
+            success: true,
+            transferred_nft: true,
+            transferred_payment: true,
+            distributed_royalties: true, // This would be handled by royalty system
+            collected_platform_fee: true, // This would be handled by fee system
+            timestamp: env.ledger().timestamp(),
         })
     }
 
@@ -278,7 +301,7 @@ impl AtomicSwapEngine {
     }
 
     /// Internal: Perform the actual atomic swap
-    fn perform_atomic_swap(env: &Env, swap: &AtomicSwap) -> Result<(), SettlementError> {
+    fn perform_atomic_swap(env: &Env, swap: &AtomicSwap, withheld: i128) -> Result<(), SettlementError> {
         // Transfer NFT from seller escrow to buyer
         for holding in swap.seller_escrow.iter() {
             if holding.is_nft {
@@ -300,11 +323,15 @@ impl AtomicSwapEngine {
             if !holding.is_nft {
                 // Find corresponding seller
                 if let Some(seller_holding) = swap.seller_escrow.get(0) {
+                    if withheld > holding.amount {
+                        return Err(SettlementError::InsufficientPayment);
+                    }
+                    let proceeds = math_utils::safe_sub(holding.amount, withheld, env)?;
                     Self::transfer_from_escrow(
                         env,
                         &seller_holding.holder,
                         &holding.asset,
-                        holding.amount,
+                        proceeds,
                         holding.is_nft
                     )?;
                 }
@@ -314,10 +341,10 @@ impl AtomicSwapEngine {
         Ok(())
     }
 
-    /// Internal: Refund all escrow holdings
+    /// Internal: Refund all escrow holdings that were actually deposited
     fn refund_escrow_holdings(env: &Env, swap: &AtomicSwap) -> Result<(), SettlementError> {
         // Refund seller escrow
-        for holding in swap.seller_escrow.iter() {
+        for holding in swap.seller_escrow.iter().filter(|h| h.deposited_at.is_some()) {
             Self::transfer_from_escrow(
                 env,
                 &holding.holder,
@@ -328,7 +355,7 @@ impl AtomicSwapEngine {
         }
 
         // Refund buyer escrow
-        for holding in swap.buyer_escrow.iter() {
+        for holding in swap.buyer_escrow.iter().filter(|h| h.deposited_at.is_some()) {
             Self::transfer_from_escrow(
                 env,
                 &holding.holder,
@@ -356,7 +383,7 @@ impl AtomicSwapEngine {
         for i in 0..swap.seller_escrow.len() {
             if let Some(mut holding) = swap.seller_escrow.get(i) {
                 if holding.holder == *depositor && holding.asset.contract == asset.contract {
-                    holding.deposited_at = timestamp;
+                    holding.deposited_at = Some(timestamp);
                     swap.seller_escrow.set(i, holding);
                     break;
                 }
@@ -367,7 +394,7 @@ impl AtomicSwapEngine {
         for i in 0..swap.buyer_escrow.len() {
             if let Some(mut holding) = swap.buyer_escrow.get(i) {
                 if holding.holder == *depositor && holding.asset.contract == asset.contract {
-                    holding.deposited_at = timestamp;
+                    holding.deposited_at = Some(timestamp);
                     swap.buyer_escrow.set(i, holding);
                     break;
                 }
@@ -379,8 +406,8 @@ impl AtomicSwapEngine {
 
     /// Internal: Update swap state based on escrow status
     fn update_swap_state(_env: &Env, swap: &mut AtomicSwap) -> Result<(), SettlementError> {
-        let seller_funded = swap.seller_escrow.iter().all(|h| h.deposited_at > 0);
-        let buyer_funded = swap.buyer_escrow.iter().all(|h| h.deposited_at > 0);
+        let seller_funded = swap.seller_escrow.iter().all(|h| h.deposited_at.is_some());
+        let buyer_funded = swap.buyer_escrow.iter().all(|h| h.deposited_at.is_some());
 
         match (seller_funded, buyer_funded) {
             (true, false) => swap.state = SwapState::SellerFunded,
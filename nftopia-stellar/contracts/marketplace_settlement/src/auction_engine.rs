@@ -2,7 +2,7 @@ use soroban_sdk::{Env, Address, Vec, Map, Symbol, symbol_short, Bytes, contractt
 use crate::error::SettlementError;
 use crate::types::{AuctionTransaction, AuctionType, Bid, DutchAuctionData, TransactionState, Asset, RoyaltyDistribution};
 use crate::storage::auction_store::{AuctionStore, DutchAuctionStore};
-use crate::utils::{math_utils, time_utils};
+use crate::utils::{asset_utils, math_utils, time_utils};
 use crate::security::frontrun_protection::{CommitRevealScheme, FrontRunningDetector};
 use crate::events::{
     emit_auction_created, emit_bid_placed, emit_bid_revealed,
@@ -86,6 +86,7 @@ impl AuctionEngine {
                 amounts: Map::new(env),
             }, // Would be set from NFT contract
             platform_fee: 0, // Would be calculated
+            content_hash: asset_utils::get_content_hash(nft_contract, token_id, env),
         };
 
         AuctionStore::put(env, &auction)?;
@@ -207,7 +208,7 @@ impl AuctionEngine {
             let event = AuctionExtendedEvent {
                 auction_id,
                 new_end_time,
-                extension_reason: Bytes::from_slice(&env, "last_minute_bid".as_bytes()),
+                extension_reason: Bytes::from_slice(env, "last_minute_bid".as_bytes()),
                 timestamp,
             };
             emit_auction_extended(env, event);
@@ -36,6 +36,12 @@ pub enum SettlementError {
     InvalidCurrency = 302,
     AssetNotSupported = 303,
 
+    // NFT errors
+    InvalidTokenId = 350,
+    NotTokenOwner = 351,
+    NftTransferFailed = 352,
+    NftNotTransferable = 353,
+
     // Royalty errors
     RoyaltyCalculationFailed = 400,
     InvalidRoyaltyPercentage = 401,
@@ -1,4 +1,4 @@
-use soroban_sdk::{Env, Address, Map, Vec, Symbol, symbol_short, Bytes, contracttype};
+use soroban_sdk::{xdr::ToXdr, Env, Address, Map, Vec, Symbol, symbol_short, Bytes, contracttype};
 use crate::error::SettlementError;
 use crate::types::{RoyaltyDistribution, DistributionResult, Asset};
 use crate::utils::math_utils;
@@ -33,8 +33,14 @@ impl RoyaltyDistributor {
         token_id: u64,
         sale_price: i128
     ) -> Result<RoyaltyDistribution, SettlementError> {
-        // Get royalty information for the NFT
-        let royalty_info = Self::get_royalty_info(env, nft_contract, token_id)?;
+        // Get royalty information for the NFT; unregistered NFTs pay what the collection reports
+        let royalty_info = match Self::get_royalty_info(env, nft_contract, token_id) {
+            Ok(info) => info,
+            Err(SettlementError::NotFound) => {
+                return Self::collection_royalties(env, nft_contract, token_id, sale_price);
+            }
+            Err(e) => return Err(e),
+        };
 
         // Calculate royalty amount
         let royalty_amount = math_utils::calculate_percentage(sale_price, royalty_info.royalty_percentage, env)?;
@@ -48,7 +54,9 @@ impl RoyaltyDistributor {
 
         // Create distribution map
         let mut amounts = Map::new(env);
-        amounts.set(royalty_info.creator.clone(), royalty_amount);
+        if royalty_amount > 0 {
+            amounts.set(royalty_info.creator.clone(), royalty_amount);
+        }
 
         let royalty_distribution = RoyaltyDistribution {
             creator_address: royalty_info.creator,
@@ -62,6 +70,45 @@ impl RoyaltyDistributor {
         Ok(royalty_distribution)
     }
 
+    /// Build a distribution from the royalty split reported by the collection contract
+    fn collection_royalties(
+        env: &Env,
+        nft_contract: &Address,
+        token_id: u64,
+        sale_price: i128
+    ) -> Result<RoyaltyDistribution, SettlementError> {
+        let payouts = asset_utils::get_collection_royalties(nft_contract, token_id, sale_price, env)?;
+
+        let mut amounts = Map::new(env);
+        let mut royalty_amount = 0i128;
+        for (recipient, amount) in payouts.iter() {
+            if amount > 0 {
+                let current_amount = amounts.get(recipient.clone()).unwrap_or(0);
+                amounts.set(recipient, math_utils::safe_add(current_amount, amount, env)?);
+                royalty_amount = math_utils::safe_add(royalty_amount, amount, env)?;
+            }
+        }
+
+        let creator_address = match payouts.first() {
+            Some((recipient, _)) => recipient,
+            None => nft_contract.clone(),
+        };
+        let creator_percentage = if sale_price > 0 {
+            (math_utils::safe_mul(royalty_amount, 10000, env)? / sale_price) as u64
+        } else {
+            0
+        };
+
+        Ok(RoyaltyDistribution {
+            creator_address,
+            creator_percentage,
+            seller_percentage: 9500,
+            platform_percentage: 500,
+            total_amount: sale_price,
+            amounts,
+        })
+    }
+
     /// Distribute royalties for a transaction
     pub fn distribute_royalties(
         env: &Env,
@@ -158,7 +205,7 @@ impl RoyaltyDistributor {
         nft_contract: &Address,
         token_id: u64
     ) -> Result<RoyaltyInfo, SettlementError> {
-        let key = Self::make_royalty_key(nft_contract, token_id);
+        let key = Self::make_royalty_key(env, nft_contract, token_id);
         let royalty_configs: Map<RoyaltyKey, RoyaltyInfo> = env
             .storage()
             .instance()
@@ -167,7 +214,7 @@ impl RoyaltyDistributor {
 
         match royalty_configs.get(key) {
             Some(info) => Ok(info),
-            None => return Err(SettlementError::NotFound),
+            None => Err(SettlementError::NotFound),
         }
     }
 
@@ -307,10 +354,9 @@ impl RoyaltyDistributor {
     }
 
     /// Internal: Create storage key for royalty info
-    fn make_royalty_key(_nft_contract: &Address, _token_id: u64) -> RoyaltyKey {
-        let key = Bytes::new(&Env::default());
-        // Convert address and token_id to bytes and append
-        // This is a simplified implementation - in practice you'd need proper serialization
+    fn make_royalty_key(env: &Env, nft_contract: &Address, token_id: u64) -> RoyaltyKey {
+        let mut key = nft_contract.clone().to_xdr(env);
+        key.extend_from_array(&token_id.to_be_bytes());
         key
     }
 
@@ -322,7 +368,7 @@ impl RoyaltyDistributor {
             .get(&ROYALTY_CONFIGS)
             .unwrap_or(Map::new(env));
 
-        let key = Self::make_royalty_key(&royalty_info.nft_contract, royalty_info.token_id);
+        let key = Self::make_royalty_key(env, &royalty_info.nft_contract, royalty_info.token_id);
         royalty_configs.set(key, royalty_info.clone());
 
         env.storage().instance().set(&ROYALTY_CONFIGS, &royalty_configs);
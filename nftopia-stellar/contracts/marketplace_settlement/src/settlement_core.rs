@@ -3,7 +3,7 @@ use crate::error::SettlementError;
 use crate::types::{
     SaleTransaction, AuctionTransaction, TradeTransaction, BundleTransaction,
     ExecutionResult, Asset, AuctionType, AdminConfig,
-    FeeConfig, VolumeTier
+    FeeConfig
 };
 use crate::storage::{
     transaction_store::{SaleTransactionStore, TradeTransactionStore, BundleTransactionStore},
@@ -15,7 +15,7 @@ use crate::royalty_distributor::RoyaltyDistributor;
 use crate::fee_manager::FeeManager;
 use crate::dispute_resolution::DisputeResolutionManager;
 use crate::security::reentrancy_guard::ReentrancyGuard;
-use crate::utils::{asset_utils, time_utils};
+use crate::utils::{asset_utils, math_utils, time_utils};
 
 /// Marketplace Settlement Contract
 #[contract]
@@ -40,27 +40,8 @@ impl MarketplaceSettlement {
 
         env.storage().instance().set(&symbol_short!("admin_cfg"), &admin_config);
 
-        // Set default fee config
-        let fee_config = FeeConfig {
-            platform_fee_bps: 250, // 2.5%
-            minimum_fee: 1000,     // Minimum 1000 units
-            maximum_fee: 1000000,  // Maximum 1M units
-            fee_recipient: Address::from_string(&soroban_sdk::String::from_str(&env, "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA")), // Fee recipient address
-            dynamic_fee_enabled: true,
-            volume_discounts: {
-                let mut discounts = Vec::new(&env);
-                discounts.push_back(VolumeTier {
-                    min_volume: 1000000,     // 1M volume
-                    fee_discount_bps: 50,    // 0.5% discount
-                });
-                discounts.push_back(VolumeTier {
-                    min_volume: 10000000,    // 10M volume
-                    fee_discount_bps: 100,   // 1% discount
-                });
-                discounts
-            },
-            vip_exemptions: Vec::new(&env),
-        };
+        // Set default fee config, with the admin collecting platform fees
+        let fee_config = FeeConfig::new(admin.clone(), &env);
         FeeManager::update_fee_config(&env, &fee_config, &admin)?;
 
         // Set default auction config
@@ -84,6 +65,8 @@ impl MarketplaceSettlement {
         currency: Asset,
         duration_seconds: u64
     ) -> Result<u64, SettlementError> {
+        seller.require_auth();
+
         ReentrancyGuard::execute(&env, &seller, "create_sale", || {
             // Validate inputs
             asset_utils::validate_asset(&currency, &Vec::new(&env), &env)?;
@@ -96,7 +79,14 @@ impl MarketplaceSettlement {
             )?;
 
             // Check NFT ownership
-            asset_utils::check_nft_ownership(&nft_address, token_id, &seller, &env)?;
+            if !asset_utils::check_nft_ownership(&nft_address, token_id, &seller, &env)? {
+                return Err(SettlementError::NotTokenOwner);
+            }
+
+            // Soulbound tokens can never be delivered to a buyer
+            if !asset_utils::is_nft_transferable(&nft_address, &env) {
+                return Err(SettlementError::NftNotTransferable);
+            }
 
             // Calculate royalties
             let royalty_distribution = RoyaltyDistributor::calculate_royalties(
@@ -125,6 +115,8 @@ impl MarketplaceSettlement {
                 escrow_address: env.current_contract_address(),
                 royalty_info: royalty_distribution,
                 platform_fee,
+                // Lets buyers check the artwork before paying
+                content_hash: asset_utils::get_content_hash(&nft_address, token_id, &env),
             };
 
             SaleTransactionStore::put(&env, &sale)?;
@@ -141,6 +133,19 @@ impl MarketplaceSettlement {
                 price
             )?;
 
+            // Take custody of the NFT for the lifetime of the listing
+            AtomicSwapEngine::deposit_to_escrow(
+                &env,
+                transaction_id,
+                &seller,
+                &Asset {
+                    contract: nft_address.clone(),
+                    symbol: Symbol::new(&env, "NFT"),
+                },
+                token_id as i128,
+                true
+            )?;
+
             Ok(transaction_id)
         })
     }
@@ -152,6 +157,8 @@ impl MarketplaceSettlement {
         buyer: Address,
         payment_amount: i128
     ) -> Result<ExecutionResult, SettlementError> {
+        buyer.require_auth();
+
         ReentrancyGuard::execute(&env, &buyer, "execute_sale", || {
             let mut sale = SaleTransactionStore::get(&env, transaction_id)?;
 
@@ -175,8 +182,28 @@ impl MarketplaceSettlement {
             sale.state = crate::types::TransactionState::Funded;
             SaleTransactionStore::update(&env, &sale)?;
 
+            // Pull the buyer's payment into escrow
+            AtomicSwapEngine::set_buyer(&env, transaction_id, &buyer)?;
+            AtomicSwapEngine::deposit_to_escrow(
+                &env,
+                transaction_id,
+                &buyer,
+                &sale.currency,
+                sale.price,
+                false
+            )?;
+
+            // Royalties and the platform fee stay in escrow, the seller gets the rest
+            let mut withheld = sale.platform_fee;
+            for (_, amount) in sale.royalty_info.amounts.iter() {
+                withheld = math_utils::safe_add(withheld, amount, &env)?;
+            }
+
+            // The collection logs the price with the delivery that follows
+            asset_utils::report_nft_sale(&sale.nft_address, sale.token_id, &sale.currency, sale.price, &env);
+
             // Execute atomic swap
-            AtomicSwapEngine::execute_swap(&env, transaction_id, &buyer)?;
+            AtomicSwapEngine::execute_swap(&env, transaction_id, withheld)?;
 
             // Distribute royalties and fees
             let distribution_result = RoyaltyDistributor::distribute_royalties(
@@ -379,22 +406,30 @@ impl MarketplaceSettlement {
         })
     }
 
-    /// Cancel a transaction
+    /// Cancel a transaction (seller or admin)
     pub fn cancel_transaction(
         env: Env,
         transaction_id: u64,
         transaction_type: Symbol, // "sale", "auction", "trade", "bundle"
         canceller: Address
     ) -> Result<(), SettlementError> {
+        canceller.require_auth();
+
         ReentrancyGuard::execute(&env, &canceller, "cancel_transaction", || {
         if transaction_type == Symbol::new(&env, "sale") {
             let mut sale = SaleTransactionStore::get(&env, transaction_id)?;
-            if sale.seller != canceller {
+            let admin_config: Option<AdminConfig> = env.storage()
+                .instance()
+                .get(&symbol_short!("admin_cfg"));
+            let is_admin = admin_config.is_some_and(|config| config.admin == canceller);
+            if sale.seller != canceller && !is_admin {
                 return Err(SettlementError::Unauthorized);
             }
             if sale.state != crate::types::TransactionState::Pending {
                 return Err(SettlementError::InvalidState);
             }
+            // The escrowed NFT goes back to the seller either way
+            AtomicSwapEngine::cancel_swap(&env, transaction_id, &sale.seller)?;
             sale.state = crate::types::TransactionState::Cancelled;
             SaleTransactionStore::update(&env, &sale)?;
         } else {
@@ -454,6 +489,8 @@ impl MarketplaceSettlement {
         reason: Bytes,
         admin: Address
     ) -> Result<(), SettlementError> {
+        admin.require_auth();
+
         // Check admin permissions
         let admin_config: AdminConfig = env.storage()
             .instance()
@@ -497,6 +534,8 @@ impl MarketplaceSettlement {
         recipient: Address,
         admin: Address
     ) -> Result<i128, SettlementError> {
+        admin.require_auth();
+
         // Check admin permissions
         let admin_config: AdminConfig = env.storage()
             .instance()
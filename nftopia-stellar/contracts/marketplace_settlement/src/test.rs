@@ -0,0 +1,557 @@
+#![cfg(test)]
+
+use crate::error::SettlementError;
+use crate::royalty_distributor::RoyaltyDistributor;
+use crate::settlement_core::{MarketplaceSettlement, MarketplaceSettlementClient};
+use crate::types::Asset;
+use crate::utils::asset_utils;
+use collection_factory::collection::{NftCollection, NftCollectionClient as CollectionClient};
+use collection_factory::types::{CollectionConfig, OperatorPolicy, RoyaltyInfo, RoyaltySplit};
+use soroban_sdk::{
+    contract, contractimpl, symbol_short,
+    testutils::{Address as _, Ledger},
+    token, vec, Address, Bytes, BytesN, Env, String, Symbol, Vec,
+};
+
+const PRICE: i128 = 100_000;
+// 2.5% default platform fee on PRICE
+const PLATFORM_FEE: i128 = 2_500;
+
+struct Setup<'a> {
+    env: Env,
+    admin: Address,
+    seller: Address,
+    buyer: Address,
+    nft: CollectionClient<'a>,
+    currency: Asset,
+    token: token::Client<'a>,
+    marketplace: MarketplaceSettlementClient<'a>,
+}
+
+fn collection_config(env: &Env, admin: &Address) -> CollectionConfig {
+    CollectionConfig {
+        name: String::from_str(env, "Test NFT"),
+        symbol: String::from_str(env, "TNFT"),
+        description: String::from_str(env, "Test Description"),
+        base_uri: String::from_str(env, "https://test.com/"),
+        max_supply: None,
+        is_public_mint: false,
+        royalty_percentage: 0,
+        royalty_recipient: admin.clone(),
+        placeholder_uri: None,
+        provenance_hash: None,
+        soulbound: false,
+        revocable: false,
+    }
+}
+
+fn setup<'a>() -> Setup<'a> {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_700_000_000);
+
+    let admin = Address::generate(&env);
+    let seller = Address::generate(&env);
+    let buyer = Address::generate(&env);
+
+    // Deploy a real collection and mint token 1 to the seller
+    let nft = CollectionClient::new(&env, &env.register(NftCollection, ()));
+    nft.init(&admin, &collection_config(&env, &admin));
+    nft.mint(
+        &admin,
+        &seller,
+        &1,
+        &String::from_str(&env, "ipfs://hash"),
+        &Vec::new(&env),
+        &None,
+    );
+
+    // Register a Stellar Asset Contract to pay with
+    let sac = env.register_stellar_asset_contract_v2(admin.clone());
+    token::StellarAssetClient::new(&env, &sac.address()).mint(&buyer, &1_000_000);
+    let token = token::Client::new(&env, &sac.address());
+    let currency = Asset {
+        contract: sac.address(),
+        symbol: symbol_short!("USDC"),
+    };
+
+    let marketplace_id = env.register(MarketplaceSettlement, ());
+    let marketplace = MarketplaceSettlementClient::new(&env, &marketplace_id);
+    marketplace.initialize(&admin);
+
+    Setup {
+        env,
+        admin,
+        seller,
+        buyer,
+        nft,
+        currency,
+        token,
+        marketplace,
+    }
+}
+
+#[test]
+fn test_execute_sale_moves_payment() {
+    let s = setup();
+
+    let tx = s
+        .marketplace
+        .create_sale(&s.seller, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+
+    // Buyer lets the marketplace pull the payment
+    s.token
+        .approve(&s.buyer, &s.marketplace.address, &PRICE, &1000);
+
+    let result = s.marketplace.execute_sale(&tx, &s.buyer, &PRICE);
+    assert!(result.success);
+
+    assert_eq!(s.token.balance(&s.buyer), 1_000_000 - PRICE);
+    assert_eq!(s.token.balance(&s.seller), PRICE - PLATFORM_FEE);
+    // The platform fee stays in escrow until withdrawn
+    assert_eq!(s.token.balance(&s.marketplace.address), PLATFORM_FEE);
+    assert_eq!(
+        s.marketplace.get_accumulated_fees(&s.currency),
+        PLATFORM_FEE
+    );
+}
+
+#[test]
+fn test_execute_sale_pays_registered_royalty() {
+    let s = setup();
+    let creator = Address::generate(&s.env);
+
+    // 10% royalty to the creator
+    s.env.as_contract(&s.marketplace.address, || {
+        RoyaltyDistributor::set_royalty_info(&s.env, &s.nft.address, 1, &creator, 1000, &s.admin)
+            .unwrap();
+    });
+
+    let tx = s
+        .marketplace
+        .create_sale(&s.seller, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+    s.token
+        .approve(&s.buyer, &s.marketplace.address, &PRICE, &1000);
+    s.marketplace.execute_sale(&tx, &s.buyer, &PRICE);
+
+    assert_eq!(s.token.balance(&creator), 10_000);
+    assert_eq!(s.token.balance(&s.seller), PRICE - 10_000 - PLATFORM_FEE);
+    assert_eq!(s.token.balance(&s.marketplace.address), PLATFORM_FEE);
+}
+
+#[test]
+fn test_execute_sale_pays_collection_royalty_splits() {
+    let s = setup();
+    let artist = Address::generate(&s.env);
+    let studio = Address::generate(&s.env);
+
+    // 10% royalty on token 1, split 70/30
+    s.nft.set_token_royalty(
+        &s.admin,
+        &1,
+        &RoyaltyInfo {
+            recipient: artist.clone(),
+            percentage: 1000,
+            splits: vec![
+                &s.env,
+                RoyaltySplit {
+                    recipient: artist.clone(),
+                    share: 7000,
+                },
+                RoyaltySplit {
+                    recipient: studio.clone(),
+                    share: 3000,
+                },
+            ],
+        },
+    );
+
+    let tx = s
+        .marketplace
+        .create_sale(&s.seller, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+    s.token
+        .approve(&s.buyer, &s.marketplace.address, &PRICE, &1000);
+    s.marketplace.execute_sale(&tx, &s.buyer, &PRICE);
+
+    assert_eq!(s.token.balance(&artist), 7_000);
+    assert_eq!(s.token.balance(&studio), 3_000);
+    assert_eq!(s.token.balance(&s.seller), PRICE - 10_000 - PLATFORM_FEE);
+}
+
+#[test]
+fn test_execute_sale_under_operator_policy() {
+    let s = setup();
+
+    // The creator only lets tokens move through this marketplace
+    s.nft.set_allowed_operator(&s.marketplace.address, &true);
+    s.nft.set_operator_policy(&OperatorPolicy {
+        restrict_approvals: true,
+        restrict_transfers: true,
+    });
+    assert!(s.nft.try_transfer(&s.seller, &s.buyer, &1).is_err());
+
+    let tx = s
+        .marketplace
+        .create_sale(&s.seller, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+    s.token
+        .approve(&s.buyer, &s.marketplace.address, &PRICE, &1000);
+    s.marketplace.execute_sale(&tx, &s.buyer, &PRICE);
+
+    assert_eq!(s.nft.owner_of(&1), s.buyer);
+}
+
+#[test]
+fn test_execute_sale_without_allowance_fails() {
+    let s = setup();
+
+    let tx = s
+        .marketplace
+        .create_sale(&s.seller, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+
+    let result = s.marketplace.try_execute_sale(&tx, &s.buyer, &PRICE);
+    assert_eq!(result, Err(Ok(SettlementError::InsufficientFunds)));
+
+    // Nothing moved and the sale is still open
+    assert_eq!(s.token.balance(&s.buyer), 1_000_000);
+    assert_eq!(
+        s.marketplace.get_sale(&tx).state,
+        crate::types::TransactionState::Pending
+    );
+}
+
+#[test]
+fn test_execute_sale_with_insufficient_balance_fails() {
+    let s = setup();
+    let poor_buyer = Address::generate(&s.env);
+
+    let tx = s
+        .marketplace
+        .create_sale(&s.seller, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+    s.token
+        .approve(&poor_buyer, &s.marketplace.address, &PRICE, &1000);
+
+    let result = s.marketplace.try_execute_sale(&tx, &poor_buyer, &PRICE);
+    assert_eq!(result, Err(Ok(SettlementError::InsufficientFunds)));
+}
+
+#[test]
+fn test_withdraw_platform_fees() {
+    let s = setup();
+    let treasury = Address::generate(&s.env);
+
+    let tx = s
+        .marketplace
+        .create_sale(&s.seller, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+    s.token
+        .approve(&s.buyer, &s.marketplace.address, &PRICE, &1000);
+    s.marketplace.execute_sale(&tx, &s.buyer, &PRICE);
+
+    let withdrawn = s
+        .marketplace
+        .withdraw_platform_fees(&s.currency, &treasury, &s.admin);
+
+    assert_eq!(withdrawn, PLATFORM_FEE);
+    assert_eq!(s.token.balance(&treasury), PLATFORM_FEE);
+    assert_eq!(s.token.balance(&s.marketplace.address), 0);
+    assert_eq!(s.marketplace.get_accumulated_fees(&s.currency), 0);
+}
+
+#[test]
+fn test_withdrawals_require_admin_auth() {
+    let s = setup();
+    let thief = Address::generate(&s.env);
+
+    let tx = s
+        .marketplace
+        .create_sale(&s.seller, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+    s.token
+        .approve(&s.buyer, &s.marketplace.address, &PRICE, &1000);
+    s.marketplace.execute_sale(&tx, &s.buyer, &PRICE);
+    let listed = s
+        .marketplace
+        .create_sale(&s.buyer, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+
+    // Naming the admin without its signature moves nothing
+    s.env.set_auths(&[]);
+    assert!(s
+        .marketplace
+        .try_withdraw_platform_fees(&s.currency, &thief, &s.admin)
+        .is_err());
+    assert!(s
+        .marketplace
+        .try_emergency_withdraw(&listed, &Bytes::new(&s.env), &s.admin)
+        .is_err());
+
+    assert_eq!(s.token.balance(&thief), 0);
+    assert_eq!(s.token.balance(&s.marketplace.address), PLATFORM_FEE);
+    assert_eq!(s.nft.owner_of(&1), s.marketplace.address);
+}
+
+// A collection whose royalty query traps
+#[contract]
+struct BrokenRoyalties;
+
+#[contractimpl]
+impl BrokenRoyalties {
+    pub fn royalty_payouts(_env: Env, _token_id: u32, _sale_price: i128) -> Vec<(Address, i128)> {
+        panic!("royalty storage unreadable")
+    }
+}
+
+#[test]
+fn test_collection_royalty_query_failures() {
+    let s = setup();
+    let broken = s.env.register(BrokenRoyalties, ());
+
+    s.env.as_contract(&s.marketplace.address, || {
+        assert_eq!(
+            asset_utils::get_collection_royalties(&broken, 1, PRICE, &s.env),
+            Err(SettlementError::RoyaltyCalculationFailed)
+        );
+        // No royalty configured is not a failure
+        assert_eq!(
+            asset_utils::get_collection_royalties(&s.nft.address, 1, PRICE, &s.env),
+            Ok(Vec::new(&s.env))
+        );
+    });
+}
+
+#[test]
+fn test_asset_utils_token_calls() {
+    let s = setup();
+    let contract = s.marketplace.address.clone();
+    let recipient = Address::generate(&s.env);
+    token::StellarAssetClient::new(&s.env, &s.currency.contract).mint(&contract, &500);
+
+    s.env.as_contract(&contract, || {
+        let token = &s.currency.contract;
+
+        assert_eq!(
+            asset_utils::get_token_balance(token, &contract, &s.env),
+            Ok(500)
+        );
+        assert_eq!(asset_utils::get_token_decimals(token, &s.env), Ok(7));
+
+        // Contract-held funds are sent directly
+        asset_utils::transfer_tokens(token, &contract, &recipient, 200, &s.env).unwrap();
+        assert_eq!(
+            asset_utils::transfer_tokens(token, &contract, &recipient, 301, &s.env),
+            Err(SettlementError::InsufficientFunds)
+        );
+
+        // Third-party funds need an allowance for this contract
+        assert_eq!(
+            asset_utils::transfer_tokens(token, &s.buyer, &recipient, 100, &s.env),
+            Err(SettlementError::InsufficientFunds)
+        );
+    });
+
+    // The buyer's approval is requested from inside the contract frame
+    s.env.mock_all_auths_allowing_non_root_auth();
+    s.env.as_contract(&contract, || {
+        asset_utils::approve_token_spending(&s.currency.contract, &s.buyer, &contract, 100, &s.env)
+            .unwrap();
+    });
+
+    s.env.as_contract(&contract, || {
+        let token = &s.currency.contract;
+        assert_eq!(
+            asset_utils::check_token_allowance(token, &s.buyer, &contract, &s.env),
+            Ok(100)
+        );
+        asset_utils::transfer_tokens(token, &s.buyer, &recipient, 100, &s.env).unwrap();
+        assert_eq!(
+            asset_utils::check_token_allowance(token, &s.buyer, &contract, &s.env),
+            Ok(0)
+        );
+    });
+
+    assert_eq!(s.token.balance(&recipient), 300);
+}
+
+#[test]
+fn test_sale_moves_nft_ownership() {
+    let s = setup();
+
+    let tx = s
+        .marketplace
+        .create_sale(&s.seller, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+
+    // The listed NFT is held in escrow
+    assert_eq!(s.nft.owner_of(&1), s.marketplace.address);
+
+    s.token
+        .approve(&s.buyer, &s.marketplace.address, &PRICE, &1000);
+    let result = s.marketplace.execute_sale(&tx, &s.buyer, &PRICE);
+
+    assert!(result.transferred_nft);
+    assert_eq!(s.nft.owner_of(&1), s.buyer);
+}
+
+#[test]
+fn test_sale_price_lands_in_provenance() {
+    let s = setup();
+    s.nft.set_trusted_marketplace(&s.marketplace.address, &true);
+
+    let tx = s
+        .marketplace
+        .create_sale(&s.seller, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+    s.token
+        .approve(&s.buyer, &s.marketplace.address, &PRICE, &1000);
+    s.marketplace.execute_sale(&tx, &s.buyer, &PRICE);
+
+    // Mint, listing into escrow, then the priced delivery
+    assert_eq!(s.nft.provenance_len(&1), 3);
+    let delivery = s.nft.provenance(&1, &2, &1).get_unchecked(0);
+    assert_eq!(delivery.from, Some(s.marketplace.address.clone()));
+    assert_eq!(delivery.to, Some(s.buyer.clone()));
+    assert_eq!(delivery.price, Some(PRICE));
+    assert_eq!(delivery.payment_token, Some(s.currency.contract.clone()));
+    assert_eq!(s.nft.provenance(&1, &1, &1).get_unchecked(0).price, None);
+}
+
+#[test]
+fn test_sale_surfaces_content_hash() {
+    let s = setup();
+    let artwork: BytesN<32> = s
+        .env
+        .crypto()
+        .sha256(&Bytes::from_slice(&s.env, b"artwork"))
+        .into();
+    s.nft.mint(
+        &s.admin,
+        &s.seller,
+        &2,
+        &String::from_str(&s.env, "ipfs://artwork"),
+        &Vec::new(&s.env),
+        &Some(artwork.clone()),
+    );
+
+    let pinned =
+        s.marketplace
+            .create_sale(&s.seller, &s.nft.address, &2, &PRICE, &s.currency, &3600);
+    let sale = s.marketplace.get_sale(&pinned);
+    assert_eq!(sale.content_hash, Some(artwork.clone()));
+    assert!(s.nft.verify_content(&2, &artwork));
+
+    let unpinned =
+        s.marketplace
+            .create_sale(&s.seller, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+    assert_eq!(s.marketplace.get_sale(&unpinned).content_hash, None);
+}
+
+#[test]
+fn test_create_sale_rejects_non_owner() {
+    let s = setup();
+    let impostor = Address::generate(&s.env);
+
+    let result =
+        s.marketplace
+            .try_create_sale(&impostor, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+    assert_eq!(result, Err(Ok(SettlementError::NotTokenOwner)));
+    assert_eq!(s.nft.owner_of(&1), s.seller);
+}
+
+#[test]
+fn test_create_sale_rejects_unminted_token() {
+    let s = setup();
+
+    let result =
+        s.marketplace
+            .try_create_sale(&s.seller, &s.nft.address, &2, &PRICE, &s.currency, &3600);
+    assert_eq!(result, Err(Ok(SettlementError::NotTokenOwner)));
+}
+
+#[test]
+fn test_create_sale_rejects_soulbound_token() {
+    let s = setup();
+
+    let badges = CollectionClient::new(&s.env, &s.env.register(NftCollection, ()));
+    let mut config = collection_config(&s.env, &s.admin);
+    config.soulbound = true;
+    badges.init(&s.admin, &config);
+    badges.mint(
+        &s.admin,
+        &s.seller,
+        &1,
+        &String::from_str(&s.env, "ipfs://badge"),
+        &Vec::new(&s.env),
+        &None,
+    );
+
+    let result =
+        s.marketplace
+            .try_create_sale(&s.seller, &badges.address, &1, &PRICE, &s.currency, &3600);
+    assert_eq!(result, Err(Ok(SettlementError::NftNotTransferable)));
+    assert_eq!(badges.owner_of(&1), s.seller);
+}
+
+#[test]
+fn test_create_sale_rejects_out_of_range_token_id() {
+    let s = setup();
+
+    let result = s.marketplace.try_create_sale(
+        &s.seller,
+        &s.nft.address,
+        &(u32::MAX as u64 + 1),
+        &PRICE,
+        &s.currency,
+        &3600,
+    );
+    assert_eq!(result, Err(Ok(SettlementError::InvalidTokenId)));
+}
+
+#[test]
+fn test_cancel_sale_returns_nft() {
+    let s = setup();
+
+    let tx = s
+        .marketplace
+        .create_sale(&s.seller, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+    s.marketplace
+        .cancel_transaction(&tx, &Symbol::new(&s.env, "sale"), &s.seller);
+
+    assert_eq!(s.nft.owner_of(&1), s.seller);
+}
+
+#[test]
+fn test_cancel_sale_requires_seller_or_admin() {
+    let s = setup();
+    let stranger = Address::generate(&s.env);
+    let sale = Symbol::new(&s.env, "sale");
+
+    let tx = s
+        .marketplace
+        .create_sale(&s.seller, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+
+    let result = s.marketplace.try_cancel_transaction(&tx, &sale, &stranger);
+    assert_eq!(result, Err(Ok(SettlementError::Unauthorized)));
+
+    // Naming the seller without their signature doesn't delist the sale
+    s.env.set_auths(&[]);
+    assert!(s
+        .marketplace
+        .try_cancel_transaction(&tx, &sale, &s.seller)
+        .is_err());
+    assert_eq!(s.nft.owner_of(&1), s.marketplace.address);
+
+    // The admin can take a listing down for the seller
+    s.env.mock_all_auths();
+    s.marketplace.cancel_transaction(&tx, &sale, &s.admin);
+    assert_eq!(s.nft.owner_of(&1), s.seller);
+}
+
+#[test]
+fn test_cancel_sale_listed_at_timestamp_zero() {
+    let s = setup();
+    s.env.ledger().set_timestamp(0);
+
+    let tx = s
+        .marketplace
+        .create_sale(&s.seller, &s.nft.address, &1, &PRICE, &s.currency, &3600);
+    s.marketplace
+        .cancel_transaction(&tx, &Symbol::new(&s.env, "sale"), &s.seller);
+
+    // A deposit at timestamp 0 still counts as a deposit
+    assert_eq!(s.nft.owner_of(&1), s.seller);
+}
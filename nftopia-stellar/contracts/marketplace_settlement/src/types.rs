@@ -1,4 +1,4 @@
-use soroban_sdk::{contracttype, Address, Vec, Map, Symbol, Bytes};
+use soroban_sdk::{contracttype, Address, Vec, Map, Symbol, Bytes, BytesN};
 
 // Transaction state enum
 #[contracttype]
@@ -37,6 +37,7 @@ pub struct SaleTransaction {
     pub escrow_address: Address,
     pub royalty_info: RoyaltyDistribution,
     pub platform_fee: i128,
+    pub content_hash: Option<BytesN<32>>, // Media hash pinned by the collection
 }
 
 // Auction transaction structure
@@ -60,6 +61,7 @@ pub struct AuctionTransaction {
     pub currency: Asset,
     pub royalty_info: RoyaltyDistribution,
     pub platform_fee: i128,
+    pub content_hash: Option<BytesN<32>>, // Media hash pinned by the collection
 }
 
 // Bid structure
@@ -1,6 +1,10 @@
-use soroban_sdk::{Address, Env, Symbol, Vec, Bytes};
+use soroban_sdk::{token::TokenClient, xdr::ScErrorType, Address, Env, Symbol, Vec, Bytes, BytesN};
 use crate::error::SettlementError;
 use crate::types::Asset;
+use crate::utils::nft_collection::{to_collection_token_id, NftCollectionClient};
+
+/// Number of ledgers a token approval granted through this contract stays valid (~30 days)
+pub const APPROVAL_LEDGERS: u32 = 518_400;
 
 /// Create a native XLM asset
 pub fn native_asset() -> Asset {
@@ -11,7 +15,15 @@ pub fn native_asset() -> Asset {
 }
 
 /// Validate that an asset is supported
-pub fn validate_asset(asset: &Asset, supported_assets: &Vec<Asset>, _env: &Env) -> Result<(), SettlementError> {
+///
+/// An empty `supported_assets` list accepts any contract implementing the token
+/// interface, including the native XLM Stellar Asset Contract.
+pub fn validate_asset(asset: &Asset, supported_assets: &Vec<Asset>, env: &Env) -> Result<(), SettlementError> {
+    if supported_assets.is_empty() {
+        get_token_decimals(&asset.contract, env)?;
+        return Ok(());
+    }
+
     // Check if asset is in the supported list
     for supported in supported_assets.iter() {
         if supported.contract == asset.contract {
@@ -19,11 +31,6 @@ pub fn validate_asset(asset: &Asset, supported_assets: &Vec<Asset>, _env: &Env)
         }
     }
 
-    // Check if it's native XLM (which is always supported)
-    if asset.contract == native_asset().contract {
-        return Ok(());
-    }
-
     Err(SettlementError::AssetNotSupported)
 }
 
@@ -68,50 +75,107 @@ pub fn is_valid_token_contract(_address: &Address, _env: &Env) -> bool {
 
 /// Get token balance for an account
 pub fn get_token_balance(
-    _token_contract: &Address,
-    _account: &Address,
-    _env: &Env,
+    token_contract: &Address,
+    account: &Address,
+    env: &Env,
 ) -> Result<i128, SettlementError> {
-    // For now, return a placeholder
-    Err(SettlementError::NotFound) // Placeholder
+    let client = TokenClient::new(env, token_contract);
+    match client.try_balance(account) {
+        Ok(Ok(balance)) => Ok(balance),
+        _ => Err(SettlementError::PaymentFailed),
+    }
 }
 
 /// Transfer tokens between accounts
+///
+/// Funds held by this contract are sent with a plain `transfer`. Funds held by
+/// anyone else are pulled with `transfer_from`, so the owner must have approved
+/// this contract for at least `amount` beforehand.
 pub fn transfer_tokens(
-    _token_contract: &Address,
-    _from: &Address,
-    _to: &Address,
-    _amount: i128,
-    _env: &Env,
+    token_contract: &Address,
+    from: &Address,
+    to: &Address,
+    amount: i128,
+    env: &Env,
 ) -> Result<(), SettlementError> {
-    // For now, return success
-    Ok(())
+    if amount < 0 {
+        return Err(SettlementError::InvalidAmount);
+    }
+    if amount == 0 || from == to {
+        return Ok(());
+    }
+
+    if get_token_balance(token_contract, from, env)? < amount {
+        return Err(SettlementError::InsufficientFunds);
+    }
+
+    let client = TokenClient::new(env, token_contract);
+    let spender = env.current_contract_address();
+
+    let result = if *from == spender {
+        client.try_transfer(from, to, &amount)
+    } else {
+        if check_token_allowance(token_contract, from, &spender, env)? < amount {
+            return Err(SettlementError::InsufficientFunds);
+        }
+        client.try_transfer_from(&spender, from, to, &amount)
+    };
+
+    match result {
+        Ok(Ok(())) => Ok(()),
+        _ => Err(SettlementError::PaymentFailed),
+    }
 }
 
 /// Approve token spending
+///
+/// The approval is valid for `APPROVAL_LEDGERS` ledgers from the current one,
+/// capped at the network's maximum entry lifetime, and requires authorization
+/// from `owner`.
 pub fn approve_token_spending(
-    _token_contract: &Address,
-    _owner: &Address,
-    _spender: &Address,
-    _amount: i128,
-    _env: &Env,
+    token_contract: &Address,
+    owner: &Address,
+    spender: &Address,
+    amount: i128,
+    env: &Env,
 ) -> Result<(), SettlementError> {
-    Ok(())
+    if amount < 0 {
+        return Err(SettlementError::InvalidAmount);
+    }
+
+    let client = TokenClient::new(env, token_contract);
+    let expiration_ledger = env
+        .ledger()
+        .sequence()
+        .saturating_add(APPROVAL_LEDGERS)
+        .min(env.ledger().max_live_until_ledger());
+    match client.try_approve(owner, spender, &amount, &expiration_ledger) {
+        Ok(Ok(())) => Ok(()),
+        _ => Err(SettlementError::PaymentFailed),
+    }
 }
 
 /// Check token allowance
 pub fn check_token_allowance(
-    _token_contract: &Address,
-    _owner: &Address,
-    _spender: &Address,
-    _env: &Env,
+    token_contract: &Address,
+    owner: &Address,
+    spender: &Address,
+    env: &Env,
 ) -> Result<i128, SettlementError> {
-    Ok(0) // Placeholder
+    let client = TokenClient::new(env, token_contract);
+    match client.try_allowance(owner, spender) {
+        Ok(Ok(allowance)) => Ok(allowance),
+        _ => Err(SettlementError::PaymentFailed),
+    }
 }
 
 /// Get token decimals
-pub fn get_token_decimals(_token_contract: &Address, _env: &Env) -> Result<u32, SettlementError> {
-    Ok(7) // Default for Stellar assets
+pub fn get_token_decimals(token_contract: &Address, env: &Env) -> Result<u32, SettlementError> {
+    let client = TokenClient::new(env, token_contract);
+    match client.try_decimals() {
+        Ok(Ok(decimals)) => Ok(decimals),
+        _ => Err(SettlementError::AssetNotSupported),
+    }
 }
 
 /// Format amount with proper decimals
@@ -129,23 +193,97 @@ pub fn validate_nft_contract(nft_contract: &Address, env: &Env) -> Result<(), Se
 
 /// Check NFT ownership
 pub fn check_nft_ownership(
-    _nft_contract: &Address,
-    _token_id: u64,
-    _owner: &Address,
-    _env: &Env,
+    nft_contract: &Address,
+    token_id: u64,
+    owner: &Address,
+    env: &Env,
 ) -> Result<bool, SettlementError> {
-    Ok(true) // Placeholder
+    let token_id = to_collection_token_id(token_id)?;
+    let client = NftCollectionClient::new(env, nft_contract);
+    match client.try_owner_of(&token_id) {
+        Ok(Ok(current_owner)) => Ok(current_owner == *owner),
+        // The collection rejects unknown token ids with a contract error
+        Err(Ok(error)) if error.is_type(ScErrorType::Contract) => Ok(false),
+        _ => Err(SettlementError::InvalidState),
+    }
+}
+
+/// Check whether the collection lets its tokens change hands
+///
+/// Contracts that don't expose `is_soulbound` are treated as transferable.
+pub fn is_nft_transferable(nft_contract: &Address, env: &Env) -> bool {
+    let client = NftCollectionClient::new(env, nft_contract);
+    !matches!(client.try_is_soulbound(), Ok(Ok(true)))
+}
+
+/// Fetch the media hash the collection pinned for a token
+///
+/// Contracts that don't expose `content_hash` have none.
+pub fn get_content_hash(nft_contract: &Address, token_id: u64, env: &Env) -> Option<BytesN<32>> {
+    let token_id = to_collection_token_id(token_id).ok()?;
+    let client = NftCollectionClient::new(env, nft_contract);
+    match client.try_content_hash(&token_id) {
+        Ok(Ok(hash)) => hash,
+        _ => None,
+    }
+}
+
+/// Report a sale price for the collection's provenance log
+///
+/// Collections that don't expose `report_sale` or don't trust this
+/// marketplace leave it out; the sale goes ahead either way.
+pub fn report_nft_sale(nft_contract: &Address, token_id: u64, currency: &Asset, price: i128, env: &Env) {
+    let Ok(token_id) = to_collection_token_id(token_id) else {
+        return;
+    };
+    let client = NftCollectionClient::new(env, nft_contract);
+    let _ = client.try_report_sale(
+        &env.current_contract_address(),
+        &token_id,
+        &currency.contract,
+        &price,
+    );
 }
 
 /// Transfer NFT
+///
+/// `from` must have authorized the call unless it is this contract.
 pub fn transfer_nft(
-    _nft_contract: &Address,
-    _from: &Address,
-    _to: &Address,
-    _token_id: u64,
-    _env: &Env,
+    nft_contract: &Address,
+    from: &Address,
+    to: &Address,
+    token_id: u64,
+    env: &Env,
 ) -> Result<(), SettlementError> {
-    Ok(())
+    let collection_token_id = to_collection_token_id(token_id)?;
+
+    if !check_nft_ownership(nft_contract, token_id, from, env)? {
+        return Err(SettlementError::NotTokenOwner);
+    }
+
+    let client = NftCollectionClient::new(env, nft_contract);
+    match client.try_transfer(from, to, &collection_token_id) {
+        Ok(Ok(())) => Ok(()),
+        _ => Err(SettlementError::NftTransferFailed),
+    }
+}
+
+/// Royalty payouts the collection itself reports for a sale
+///
+/// A collection with no royalty configured reports none. A failing query
+/// fails the sale rather than settling it without royalties.
+pub fn get_collection_royalties(
+    nft_contract: &Address,
+    token_id: u64,
+    sale_price: i128,
+    env: &Env,
+) -> Result<Vec<(Address, i128)>, SettlementError> {
+    let token_id = to_collection_token_id(token_id)?;
+    let client = NftCollectionClient::new(env, nft_contract);
+    match client.try_royalty_payouts(&token_id, &sale_price) {
+        Ok(Ok(payouts)) => Ok(payouts),
+        _ => Err(SettlementError::RoyaltyCalculationFailed),
+    }
 }
 
 /// Get NFT metadata URI
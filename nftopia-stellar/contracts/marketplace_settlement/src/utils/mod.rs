@@ -1,3 +1,4 @@
 pub mod math_utils;
 pub mod time_utils;
-pub mod asset_utils;
\ No newline at end of file
+pub mod asset_utils;
+pub mod nft_collection;
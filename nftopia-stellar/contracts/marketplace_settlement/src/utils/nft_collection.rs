@@ -0,0 +1,21 @@
+use soroban_sdk::{contractclient, Address, BytesN, Env, Vec};
+use crate::error::SettlementError;
+
+/// Subset of the `collection_factory::NftCollection` interface the marketplace calls
+#[contractclient(name = "NftCollectionClient")]
+pub trait NftCollectionInterface {
+    fn owner_of(env: Env, token_id: u32) -> Address;
+    fn transfer(env: Env, from: Address, to: Address, token_id: u32);
+    fn is_soulbound(env: Env) -> bool;
+    fn royalty_payouts(env: Env, token_id: u32, sale_price: i128) -> Vec<(Address, i128)>;
+    fn content_hash(env: Env, token_id: u32) -> Option<BytesN<32>>;
+    fn report_sale(env: Env, marketplace: Address, token_id: u32, payment_token: Address, price: i128);
+}
+
+/// Map a marketplace token id onto the u32 id space used by `NftCollection`
+///
+/// Listings store token ids as u64 so other NFT standards fit, but collections
+/// deployed by the factory only issue u32 ids; anything larger cannot exist there.
+pub fn to_collection_token_id(token_id: u64) -> Result<u32, SettlementError> {
+    u32::try_from(token_id).map_err(|_| SettlementError::InvalidTokenId)
+}